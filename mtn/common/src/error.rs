@@ -0,0 +1,190 @@
+use std::error;
+use std::fmt;
+
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::FailureReason;
+
+pub type Result<T, E = MomoError> = std::result::Result<T, E>;
+
+// NOTE: the body MTN sends along with most 4xx and 5xx responses, e.g.
+// {"code":"RESOURCE_ALREADY_EXIST","message":"Duplicated reference id."}
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ErrorResponse {
+    pub code: FailureReason,
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawErrorResponse {
+    code: String,
+    message: Option<String>,
+}
+
+impl ErrorResponse {
+    // NOTE: None for bodies without an error code, e.g. the ones the api
+    // gateway sends for an invalid subscription key, the raw body is kept
+    // on the ApiError either way
+    pub fn parse(body: &str) -> Option<ErrorResponse> {
+        let raw: RawErrorResponse = serde_json::from_str(body).ok()?;
+
+        Some(ErrorResponse {
+            code: FailureReason::from(&raw.code[..]),
+            message: raw.message,
+        })
+    }
+}
+
+// NOTE: an MTN api call answered with an unexpected http status
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ApiError {
+    pub what: String,
+    pub status: StatusCode,
+    pub reference_id: Option<Uuid>,
+    pub context: Option<String>,
+    pub error_response: Option<ErrorResponse>,
+    pub body: String,
+}
+
+// NOTE: why a request never got a response
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum NetworkFailure {
+    Connect,
+    Timeout,
+    Other,
+}
+
+// NOTE: why a consent request ended without the account holder consenting
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConsentError {
+    Denied,
+    Expired,
+}
+
+impl fmt::Display for ConsentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConsentError::Denied => write!(f, "account holder denied consent"),
+            ConsentError::Expired => {
+                write!(f, "consent request expired before being answered")
+            }
+        }
+    }
+}
+
+impl error::Error for ConsentError {}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MomoError {
+    // NOTE: input rejected before anything was sent, e.g. an invalid msisdn
+    Validation(String),
+    // NOTE: the client is not set up for the requested operation, e.g. no
+    // credentials for a product
+    Config(String),
+    Api(Box<ApiError>),
+    Network {
+        failure: NetworkFailure,
+        message: String,
+    },
+    // NOTE: MTN answered as expected but with a body we do not understand
+    Decode(String),
+    Consent(ConsentError),
+}
+
+impl MomoError {
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            MomoError::Api(api_error) => Some(api_error.status),
+            _ => None,
+        }
+    }
+
+    pub fn reference_id(&self) -> Option<Uuid> {
+        match self {
+            MomoError::Api(api_error) => api_error.reference_id,
+            _ => None,
+        }
+    }
+
+    pub fn error_response(&self) -> Option<&ErrorResponse> {
+        match self {
+            MomoError::Api(api_error) => api_error.error_response.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> Option<&FailureReason> {
+        self.error_response()
+            .map(|error_response| &error_response.code)
+    }
+
+    // NOTE: whether sending the very same request again might succeed,
+    // i.e. MTN being overloaded or the network acting up
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            MomoError::Api(api_error) => {
+                api_error.status == StatusCode::TOO_MANY_REQUESTS
+                    || api_error.status.is_server_error()
+            }
+            MomoError::Network { failure, .. } => match failure {
+                NetworkFailure::Connect | NetworkFailure::Timeout => true,
+                NetworkFailure::Other => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for MomoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MomoError::Validation(message) | MomoError::Config(message) => {
+                write!(f, "{}", message)
+            }
+            MomoError::Api(api_error) => {
+                write!(
+                    f,
+                    "{} failed - http status {:?}",
+                    api_error.what, api_error.status
+                )?;
+
+                if let Some(reference_id) = api_error.reference_id {
+                    write!(f, " - reference id {}", reference_id)?;
+                }
+
+                if let Some(context) = &api_error.context {
+                    write!(f, " - {}", context)?;
+                }
+
+                match &api_error.error_response {
+                    Some(ErrorResponse {
+                        code,
+                        message: Some(message),
+                    }) => write!(f, "\n{} - {}", code, message),
+                    Some(ErrorResponse {
+                        code,
+                        message: None,
+                    }) => write!(f, "\n{}", code),
+                    None => write!(f, "\n{}", api_error.body),
+                }
+            }
+            MomoError::Network { failure, message } => {
+                write!(f, "request failed ({:?}) - {}", failure, message)
+            }
+            MomoError::Decode(message) => {
+                write!(f, "unexpected response body - {}", message)
+            }
+            MomoError::Consent(consent_error) => consent_error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for MomoError {}
+
+impl From<ConsentError> for MomoError {
+    fn from(consent_error: ConsentError) -> MomoError {
+        MomoError::Consent(consent_error)
+    }
+}
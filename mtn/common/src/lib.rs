@@ -1,13 +1,15 @@
 use std::fmt;
+use std::mem;
 use std::str::FromStr;
 
-use anyhow::{bail, Error, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+mod error;
 mod util;
+pub use error::*;
 use util::strip_lead_char;
 
 lazy_static! {
@@ -15,6 +17,10 @@ lazy_static! {
         Url::parse("https://www.mocky.io/v2/5ec0fa1c2f000079004c86fb")
             .expect("url");
     static ref NUMBERS_ONLY: Regex = Regex::new("[^0-9]+").expect("regex");
+    static ref EMAIL: Regex =
+        Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").expect("regex");
+    static ref PARTY_CODE: Regex =
+        Regex::new(r"^[A-Za-z0-9_\-]{1,64}$").expect("regex");
 }
 
 pub const FALLBACK_CALLBACK_HOST: &str = "www.mocky.io";
@@ -29,20 +35,69 @@ pub const SANDBOX: &str = "sandbox";
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum PaymentStatus {
-    Resolved,
-    Rejected,
+    Created,
     Pending,
+    Ongoing,
+    Resolved,
+    Rejected(Option<FailureReason>),
+    // NOTE: MTN's REJECTED, the payer declined the approval prompt
+    Declined(Option<FailureReason>),
+    TimedOut,
+    // any status MTN introduces after this enum was last updated
+    Unknown(String),
+}
+
+impl PaymentStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            PaymentStatus::Resolved
+                | PaymentStatus::Rejected(_)
+                | PaymentStatus::Declined(_)
+                | PaymentStatus::TimedOut
+        )
+    }
+
+    // NOTE: statuses only ever move forward: created, pending, ongoing and
+    // then one of the final statuses, reporting the same status again is
+    // always fine while an unknown status can neither be ruled in nor out
+    // unless the payment has already been finalized
+    pub fn can_transition_to(&self, next: &PaymentStatus) -> bool {
+        if mem::discriminant(self) == mem::discriminant(next) {
+            true
+        } else if self.is_final() {
+            false
+        } else if let (Some(from), Some(to)) = (self.stage(), next.stage()) {
+            from < to
+        } else {
+            true
+        }
+    }
+
+    fn stage(&self) -> Option<u8> {
+        match self {
+            PaymentStatus::Created => Some(0u8),
+            PaymentStatus::Pending => Some(1u8),
+            PaymentStatus::Ongoing => Some(2u8),
+            PaymentStatus::Unknown(_) => None,
+            _ => Some(3u8),
+        }
+    }
 }
 
 impl FromStr for PaymentStatus {
-    type Err = Error;
+    type Err = MomoError;
 
     fn from_str(status: &str) -> Result<PaymentStatus> {
         let payment_status: PaymentStatus = match status {
-            "SUCCESSFUL" => PaymentStatus::Resolved,
-            "FAILED" => PaymentStatus::Rejected,
+            "CREATED" => PaymentStatus::Created,
             "PENDING" => PaymentStatus::Pending,
-            _ => bail!("unknown payment status {:?}", status),
+            "ONGOING" => PaymentStatus::Ongoing,
+            "SUCCESSFUL" => PaymentStatus::Resolved,
+            "FAILED" => PaymentStatus::Rejected(None),
+            "REJECTED" => PaymentStatus::Declined(None),
+            "TIMEOUT" => PaymentStatus::TimedOut,
+            _ => PaymentStatus::Unknown(status.to_string()),
         };
 
         Ok(payment_status)
@@ -52,9 +107,129 @@ impl FromStr for PaymentStatus {
 impl fmt::Display for PaymentStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s: &str = match self {
-            PaymentStatus::Resolved => "SUCCESSFUL",
-            PaymentStatus::Rejected => "FAILED",
+            PaymentStatus::Created => "CREATED",
             PaymentStatus::Pending => "PENDING",
+            PaymentStatus::Ongoing => "ONGOING",
+            PaymentStatus::Resolved => "SUCCESSFUL",
+            PaymentStatus::Rejected(_) => "FAILED",
+            PaymentStatus::Declined(_) => "REJECTED",
+            PaymentStatus::TimedOut => "TIMEOUT",
+            PaymentStatus::Unknown(status) => status,
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// NOTE: the reason codes MTN documents for failed transactions and error
+// responses, any code not listed here is kept verbatim in
+// FailureReason::Other
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum FailureReason {
+    PayeeNotFound,
+    PayerNotFound,
+    NotAllowed,
+    NotAllowedTargetEnvironment,
+    InvalidCallbackUrlHost,
+    InvalidCurrency,
+    ServiceUnavailable,
+    InternalProcessingError,
+    NotEnoughFunds,
+    PayerLimitReached,
+    PayeeNotAllowedToReceive,
+    PaymentNotApproved,
+    ResourceNotFound,
+    ApprovalRejected,
+    Expired,
+    TransactionCanceled,
+    ResourceAlreadyExist,
+    CouldNotPerformTransaction,
+    Other(String),
+}
+
+impl FailureReason {
+    // NOTE: retryable means that resubmitting the same payment may
+    // succeed without the payer or payee changing anything, a payer
+    // with insufficient funds or a reached limit has to act first
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            FailureReason::ServiceUnavailable
+                | FailureReason::InternalProcessingError
+                | FailureReason::CouldNotPerformTransaction
+                | FailureReason::Expired
+        )
+    }
+}
+
+impl From<&str> for FailureReason {
+    fn from(code: &str) -> FailureReason {
+        match code {
+            "PAYEE_NOT_FOUND" => FailureReason::PayeeNotFound,
+            "PAYER_NOT_FOUND" => FailureReason::PayerNotFound,
+            "NOT_ALLOWED" => FailureReason::NotAllowed,
+            "NOT_ALLOWED_TARGET_ENVIRONMENT" => {
+                FailureReason::NotAllowedTargetEnvironment
+            }
+            "INVALID_CALLBACK_URL_HOST" => {
+                FailureReason::InvalidCallbackUrlHost
+            }
+            "INVALID_CURRENCY" => FailureReason::InvalidCurrency,
+            "SERVICE_UNAVAILABLE" => FailureReason::ServiceUnavailable,
+            "INTERNAL_PROCESSING_ERROR" => {
+                FailureReason::InternalProcessingError
+            }
+            "NOT_ENOUGH_FUNDS" => FailureReason::NotEnoughFunds,
+            "PAYER_LIMIT_REACHED" => FailureReason::PayerLimitReached,
+            "PAYEE_NOT_ALLOWED_TO_RECEIVE" => {
+                FailureReason::PayeeNotAllowedToReceive
+            }
+            "PAYMENT_NOT_APPROVED" => FailureReason::PaymentNotApproved,
+            "RESOURCE_NOT_FOUND" => FailureReason::ResourceNotFound,
+            "APPROVAL_REJECTED" => FailureReason::ApprovalRejected,
+            "EXPIRED" => FailureReason::Expired,
+            "TRANSACTION_CANCELED" => FailureReason::TransactionCanceled,
+            "RESOURCE_ALREADY_EXIST" => FailureReason::ResourceAlreadyExist,
+            "COULD_NOT_PERFORM_TRANSACTION" => {
+                FailureReason::CouldNotPerformTransaction
+            }
+            _ => FailureReason::Other(code.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s: &str = match self {
+            FailureReason::PayeeNotFound => "PAYEE_NOT_FOUND",
+            FailureReason::PayerNotFound => "PAYER_NOT_FOUND",
+            FailureReason::NotAllowed => "NOT_ALLOWED",
+            FailureReason::NotAllowedTargetEnvironment => {
+                "NOT_ALLOWED_TARGET_ENVIRONMENT"
+            }
+            FailureReason::InvalidCallbackUrlHost => {
+                "INVALID_CALLBACK_URL_HOST"
+            }
+            FailureReason::InvalidCurrency => "INVALID_CURRENCY",
+            FailureReason::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+            FailureReason::InternalProcessingError => {
+                "INTERNAL_PROCESSING_ERROR"
+            }
+            FailureReason::NotEnoughFunds => "NOT_ENOUGH_FUNDS",
+            FailureReason::PayerLimitReached => "PAYER_LIMIT_REACHED",
+            FailureReason::PayeeNotAllowedToReceive => {
+                "PAYEE_NOT_ALLOWED_TO_RECEIVE"
+            }
+            FailureReason::PaymentNotApproved => "PAYMENT_NOT_APPROVED",
+            FailureReason::ResourceNotFound => "RESOURCE_NOT_FOUND",
+            FailureReason::ApprovalRejected => "APPROVAL_REJECTED",
+            FailureReason::Expired => "EXPIRED",
+            FailureReason::TransactionCanceled => "TRANSACTION_CANCELED",
+            FailureReason::ResourceAlreadyExist => "RESOURCE_ALREADY_EXIST",
+            FailureReason::CouldNotPerformTransaction => {
+                "COULD_NOT_PERFORM_TRANSACTION"
+            }
+            FailureReason::Other(code) => code,
         };
 
         write!(f, "{}", s)
@@ -68,13 +243,18 @@ pub enum Currency {
 }
 
 impl FromStr for Currency {
-    type Err = Error;
+    type Err = MomoError;
 
     fn from_str(currency_str: &str) -> Result<Currency> {
         let currency: Currency = match currency_str {
             "GHS" => Currency::Cedi,
             "NGN" => Currency::Naira,
-            _ => bail!("unknown currency {:?}", currency_str),
+            _ => {
+                return Err(MomoError::Validation(format!(
+                    "unknown currency {:?}",
+                    currency_str
+                )))
+            }
         };
 
         Ok(currency)
@@ -92,6 +272,85 @@ impl fmt::Display for Currency {
     }
 }
 
+// NOTE: (de)serialized the way MTN spells it, e.g. "MSISDN", any id type
+// not listed here is kept verbatim in PartyIdType::Other
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum PartyIdType {
+    Msisdn,
+    Email,
+    PartyCode,
+    Other(String),
+}
+
+impl PartyIdType {
+    // NOTE: account holder urls take the id type in lower case
+    pub fn path_segment(&self) -> &str {
+        match self {
+            PartyIdType::Msisdn => "msisdn",
+            PartyIdType::Email => "email",
+            PartyIdType::PartyCode => "party_code",
+            PartyIdType::Other(party_id_type) => party_id_type,
+        }
+    }
+}
+
+impl From<&str> for PartyIdType {
+    fn from(party_id_type: &str) -> PartyIdType {
+        match party_id_type {
+            "MSISDN" => PartyIdType::Msisdn,
+            "EMAIL" => PartyIdType::Email,
+            "PARTY_CODE" => PartyIdType::PartyCode,
+            _ => PartyIdType::Other(party_id_type.to_string()),
+        }
+    }
+}
+
+impl From<String> for PartyIdType {
+    fn from(party_id_type: String) -> PartyIdType {
+        PartyIdType::from(&party_id_type[..])
+    }
+}
+
+impl From<PartyIdType> for String {
+    fn from(party_id_type: PartyIdType) -> String {
+        party_id_type.to_string()
+    }
+}
+
+impl FromStr for PartyIdType {
+    type Err = MomoError;
+
+    fn from_str(party_id_type_str: &str) -> Result<PartyIdType> {
+        let party_id_type: PartyIdType = match party_id_type_str {
+            "MSISDN" => PartyIdType::Msisdn,
+            "EMAIL" => PartyIdType::Email,
+            "PARTY_CODE" => PartyIdType::PartyCode,
+            _ => {
+                return Err(MomoError::Validation(format!(
+                    "unknown party id type {:?}",
+                    party_id_type_str
+                )))
+            }
+        };
+
+        Ok(party_id_type)
+    }
+}
+
+impl fmt::Display for PartyIdType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s: &str = match self {
+            PartyIdType::Msisdn => "MSISDN",
+            PartyIdType::Email => "EMAIL",
+            PartyIdType::PartyCode => "PARTY_CODE",
+            PartyIdType::Other(party_id_type) => party_id_type,
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Country {
     pub code: String,
@@ -110,7 +369,7 @@ impl Msisdn {
     ) -> Result<Msisdn> {
         let rebase: &str = &NUMBERS_ONLY.replace_all(mobile_number, "");
 
-        let mut rebase: &str = strip_lead_char(&rebase, '0', true);
+        let mut rebase: &str = strip_lead_char(rebase, '0', true);
 
         if let Some(countries) = alternate_countries {
             for country in countries {
@@ -123,12 +382,11 @@ impl Msisdn {
         }
 
         if rebase.len() < default_country.non_prefix_digits {
-            bail!(
+            return Err(MomoError::Validation(format!(
                 "mobile number {} has too few \
                  non prefix digits for default {:?}",
-                mobile_number,
-                default_country
-            );
+                mobile_number, default_country
+            )));
         } else if rebase.len() > default_country.non_prefix_digits {
             for character in default_country.prefix.chars() {
                 rebase = strip_lead_char(rebase, character, false);
@@ -137,12 +395,11 @@ impl Msisdn {
             rebase = strip_lead_char(rebase, '0', false);
 
             if rebase.len() != default_country.non_prefix_digits {
-                bail!(
+                return Err(MomoError::Validation(format!(
                     "mobile number {} has an incorrect number of \
                      non prefix digits for default {:?}",
-                    mobile_number,
-                    default_country
-                );
+                    mobile_number, default_country
+                )));
             }
         }
 
@@ -156,6 +413,102 @@ impl fmt::Display for Msisdn {
     }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Email(String);
+
+impl Email {
+    pub fn parse(email: &str) -> Result<Email> {
+        let trimmed: &str = email.trim();
+
+        // NOTE: 254 characters is the maximum length of a forward path
+        if trimmed.len() > 254usize || !EMAIL.is_match(trimmed) {
+            return Err(MomoError::Validation(format!(
+                "{:?} is not a valid email address",
+                email
+            )));
+        }
+
+        Ok(Email(trimmed.to_string()))
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0).fmt(f)
+    }
+}
+
+// NOTE: a merchant alias that MTN accepts in place of a phone number
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PartyCode(String);
+
+impl PartyCode {
+    pub fn parse(party_code: &str) -> Result<PartyCode> {
+        if !PARTY_CODE.is_match(party_code) {
+            return Err(MomoError::Validation(format!(
+                "party code {:?} must be 1 to 64 ascii letters, digits, \
+                 dashes or underscores",
+                party_code
+            )));
+        }
+
+        Ok(PartyCode(party_code.to_string()))
+    }
+}
+
+impl fmt::Display for PartyCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0).fmt(f)
+    }
+}
+
+// NOTE: a payer or payee as identified towards MTN, its Display impl
+// yields the party id
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Party {
+    Msisdn(Msisdn),
+    Email(Email),
+    PartyCode(PartyCode),
+}
+
+impl Party {
+    pub fn id_type(&self) -> PartyIdType {
+        match self {
+            Party::Msisdn(_) => PartyIdType::Msisdn,
+            Party::Email(_) => PartyIdType::Email,
+            Party::PartyCode(_) => PartyIdType::PartyCode,
+        }
+    }
+}
+
+impl From<Msisdn> for Party {
+    fn from(msisdn: Msisdn) -> Party {
+        Party::Msisdn(msisdn)
+    }
+}
+
+impl From<Email> for Party {
+    fn from(email: Email) -> Party {
+        Party::Email(email)
+    }
+}
+
+impl From<PartyCode> for Party {
+    fn from(party_code: PartyCode) -> Party {
+        Party::PartyCode(party_code)
+    }
+}
+
+impl fmt::Display for Party {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Party::Msisdn(msisdn) => msisdn.fmt(f),
+            Party::Email(email) => email.fmt(f),
+            Party::PartyCode(party_code) => party_code.fmt(f),
+        }
+    }
+}
+
 #[allow(non_snake_case)]
 #[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Balance {
@@ -171,7 +524,7 @@ mod mtn_momo_common_unit_tests {
     mod msisdn {
         // use std::str::FromStr;
 
-        use crate::{Country, Msisdn};
+        use crate::{Country, MomoError, Msisdn};
         use lazy_static::lazy_static;
         use proptest::prelude::*;
         use regex::Regex;
@@ -251,6 +604,14 @@ mod mtn_momo_common_unit_tests {
                 assert!(Msisdn::parse(&s, &GHANA, None).is_err());
             }
         }
+
+        #[test]
+        fn invalid_msisdns_fail_validation() {
+            match Msisdn::parse("054237", &GHANA, None) {
+                Err(MomoError::Validation(_)) => {}
+                other => panic!("expected a validation error, got {:?}", other),
+            }
+        }
     }
 
     mod payment_status {
@@ -259,11 +620,225 @@ mod mtn_momo_common_unit_tests {
         use crate::PaymentStatus;
         use proptest::prelude::*;
 
+        const STATUSES: [&str; 7] = [
+            "CREATED",
+            "PENDING",
+            "ONGOING",
+            "SUCCESSFUL",
+            "FAILED",
+            "REJECTED",
+            "TIMEOUT",
+        ];
+
+        fn status(s: &str) -> PaymentStatus {
+            PaymentStatus::from_str(s).expect("status")
+        }
+
+        #[test]
+        fn roundtripping_documented_statuses() {
+            for s in STATUSES.iter() {
+                assert_eq!(&status(s).to_string(), s);
+                assert_ne!(status(s), PaymentStatus::Unknown(s.to_string()));
+            }
+        }
+
+        #[test]
+        fn moving_forward() {
+            assert!(status("CREATED").can_transition_to(&status("PENDING")));
+            assert!(status("PENDING").can_transition_to(&status("ONGOING")));
+            assert!(status("PENDING").can_transition_to(&status("SUCCESSFUL")));
+            assert!(status("ONGOING").can_transition_to(&status("TIMEOUT")));
+            assert!(status("CREATED").can_transition_to(&status("REJECTED")));
+        }
+
+        #[test]
+        fn moving_backward_is_flagged() {
+            assert!(!status("SUCCESSFUL").can_transition_to(&status("PENDING")));
+            assert!(!status("FAILED").can_transition_to(&status("SUCCESSFUL")));
+            assert!(!status("ONGOING").can_transition_to(&status("CREATED")));
+            assert!(!status("TIMEOUT").can_transition_to(&status("NEW")));
+        }
+
         proptest! {
             #[test]
-            fn from_str_fails_on_unicode(s in "\\PC*")  {
-                assert!(PaymentStatus::from_str(&s).is_err());
+            fn from_str_falls_back_to_unknown(s in "\\PC*")  {
+                prop_assume!(!STATUSES.contains(&&s[..]));
+
+                assert_eq!(
+                    PaymentStatus::from_str(&s).expect("status"),
+                    PaymentStatus::Unknown(s.clone())
+                );
+            }
+
+            #[test]
+            fn repeating_a_status_is_fine(i in 0usize..7usize) {
+                assert!(status(STATUSES[i]).can_transition_to(&status(STATUSES[i])));
+            }
+
+            #[test]
+            fn unknown_statuses_never_reopen_final_ones(
+                i in 3usize..7usize,
+                s in "[A-Z]{16}"
+            ) {
+                let unknown: PaymentStatus = PaymentStatus::Unknown(s);
+
+                assert!(!status(STATUSES[i]).can_transition_to(&unknown));
+                assert!(unknown.can_transition_to(&status(STATUSES[i])));
             }
         }
     }
+
+    mod party {
+        use crate::{Email, Party, PartyCode, PartyIdType};
+        use proptest::prelude::*;
+
+        #[test]
+        fn party_id_types() {
+            let email: Party = Party::from(
+                Email::parse("kwame@nugget.digital").expect("email"),
+            );
+            let party_code: Party =
+                Party::from(PartyCode::parse("NUGGET_DIGITAL").expect("code"));
+
+            assert_eq!(email.id_type(), PartyIdType::Email);
+            assert_eq!(email.to_string(), "kwame@nugget.digital");
+            assert_eq!(party_code.id_type(), PartyIdType::PartyCode);
+            assert_eq!(party_code.to_string(), "NUGGET_DIGITAL");
+        }
+
+        #[test]
+        fn deserializing_party_id_types() {
+            let msisdn: PartyIdType =
+                serde_json::from_str(r#""MSISDN""#).expect("party id type");
+            let other: PartyIdType =
+                serde_json::from_str(r#""ALIAS""#).expect("party id type");
+
+            assert_eq!(msisdn, PartyIdType::Msisdn);
+            assert_eq!(other, PartyIdType::Other(String::from("ALIAS")));
+            assert_eq!(
+                serde_json::to_string(&PartyIdType::PartyCode).expect("json"),
+                r#""PARTY_CODE""#
+            );
+        }
+
+        #[test]
+        fn email_fails_on_invalid_addresses() {
+            assert!(Email::parse("").is_err());
+            assert!(Email::parse("kwame").is_err());
+            assert!(Email::parse("kwame@nugget").is_err());
+            assert!(Email::parse("kwame mensah@nugget.digital").is_err());
+        }
+
+        proptest! {
+            #[test]
+            fn email_from_valid_addresses(s in "[a-z0-9.]{1,32}@[a-z0-9]{1,32}\\.[a-z]{2,8}") {
+                assert_eq!(Email::parse(&s).expect("email").to_string(), s);
+            }
+
+            #[test]
+            fn party_code_fails_on_unsupported_characters(s in "[^A-Za-z0-9_\\-]+") {
+                assert!(PartyCode::parse(&s).is_err());
+            }
+        }
+    }
+
+    mod failure_reason {
+        use crate::FailureReason;
+        use proptest::prelude::*;
+
+        #[test]
+        fn from_documented_codes() {
+            assert_eq!(
+                FailureReason::from("NOT_ENOUGH_FUNDS"),
+                FailureReason::NotEnoughFunds
+            );
+            assert_eq!(
+                FailureReason::from("APPROVAL_REJECTED"),
+                FailureReason::ApprovalRejected
+            );
+            assert_eq!(FailureReason::from("EXPIRED"), FailureReason::Expired);
+        }
+
+        #[test]
+        fn retryable_reasons() {
+            assert!(FailureReason::Expired.is_retryable());
+            assert!(FailureReason::ServiceUnavailable.is_retryable());
+            assert!(!FailureReason::PayerNotFound.is_retryable());
+            assert!(!FailureReason::NotEnoughFunds.is_retryable());
+            assert!(!FailureReason::Other(String::from("X")).is_retryable());
+        }
+
+        proptest! {
+            #[test]
+            fn roundtripping_any_code(s in "[A-Z_]{1,32}") {
+                assert_eq!(FailureReason::from(&s[..]).to_string(), s);
+            }
+        }
+    }
+
+    mod momo_error {
+        use crate::{
+            ApiError, ConsentError, ErrorResponse, FailureReason, MomoError,
+            NetworkFailure,
+        };
+        use http::StatusCode;
+
+        fn api_error(status: StatusCode) -> MomoError {
+            MomoError::Api(Box::new(ApiError {
+                what: String::from("requesting transaction status"),
+                status,
+                reference_id: None,
+                context: None,
+                error_response: None,
+                body: String::new(),
+            }))
+        }
+
+        #[test]
+        fn retryable_errors() {
+            assert!(api_error(StatusCode::INTERNAL_SERVER_ERROR).is_retryable());
+            assert!(api_error(StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+            assert!(api_error(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+            assert!(!api_error(StatusCode::CONFLICT).is_retryable());
+            assert!(!api_error(StatusCode::UNAUTHORIZED).is_retryable());
+            assert!(MomoError::Network {
+                failure: NetworkFailure::Timeout,
+                message: String::from("timed out"),
+            }
+            .is_retryable());
+            assert!(!MomoError::from(ConsentError::Denied).is_retryable());
+        }
+
+        #[test]
+        fn parsing_error_responses() {
+            assert_eq!(
+                ErrorResponse::parse(
+                    r#"{
+                        "code": "PAYEE_NOT_FOUND",
+                        "message": "Payee does not exist"
+                    }"#
+                ),
+                Some(ErrorResponse {
+                    code: FailureReason::PayeeNotFound,
+                    message: Some(String::from("Payee does not exist")),
+                })
+            );
+            assert_eq!(
+                ErrorResponse::parse(r#"{ "code": "NOT_YET_DOCUMENTED" }"#),
+                Some(ErrorResponse {
+                    code: FailureReason::Other(String::from(
+                        "NOT_YET_DOCUMENTED"
+                    )),
+                    message: None,
+                })
+            );
+            assert_eq!(
+                ErrorResponse::parse(
+                    r#"{ "statusCode": 401, "message": "Access denied" }"#
+                ),
+                None
+            );
+            assert_eq!(ErrorResponse::parse("<html></html>"), None);
+        }
+    }
 }
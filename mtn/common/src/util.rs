@@ -1,5 +1,5 @@
 pub fn strip_lead_char(string: &str, character: char, multiple: bool) -> &str {
-    if string.len() == 0 {
+    if string.is_empty() {
         return string;
     }
 
@@ -9,7 +9,7 @@ pub fn strip_lead_char(string: &str, character: char, multiple: bool) -> &str {
     // NOTE: setting var i to the new string slice head
     loop {
         if bytes[i] as char == character {
-            i = i + 1usize;
+            i += 1usize;
 
             if i >= bytes.len() || !multiple {
                 break;
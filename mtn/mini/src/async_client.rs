@@ -0,0 +1,687 @@
+use std::sync::Arc;
+
+use log::debug;
+use url::Url;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::collection;
+use crate::consent::{ConsentPoll, ConsentPoller, ConsentStep};
+use crate::disbursement;
+use crate::remittance;
+use crate::session::AsyncSession;
+use crate::transport::{AsyncReqwestTransport, AsyncTransport};
+use crate::{
+    ApiVersion, ApprovedPreApproval, BasicUserInfo, BillPayment,
+    BillPaymentDetails, CashTransfer, CashTransferDetails, Config,
+    ConsentRequest, ConsentToken, Invoice, InvoiceDetails, PaymentRequest,
+    PreApproval, PreApprovalDetails, Product, Refund, RefundRequest, Scope,
+    Transaction, Transfer, TransferRequest, UserInfoWithConsent,
+};
+
+// NOTE: the operations of IClient for use inside an async runtime,
+// requests are built and responses parsed by the very same code the
+// blocking Client uses
+#[derive(Debug)]
+pub struct AsyncClient {
+    pub target_environment: String,
+    pub base_url: String,
+    pub callback_host: String,
+    collections: AsyncSession,
+    disbursements: Option<AsyncDisbursementClient>,
+    remittances: Option<AsyncRemittanceClient>,
+}
+
+#[derive(Debug)]
+pub struct AsyncDisbursementClient {
+    pub target_environment: String,
+    pub base_url: String,
+    pub callback_host: String,
+    disbursements: AsyncSession,
+}
+
+#[derive(Debug)]
+pub struct AsyncRemittanceClient {
+    pub target_environment: String,
+    pub base_url: String,
+    pub callback_host: String,
+    remittances: AsyncSession,
+}
+
+impl AsyncClient {
+    pub async fn new(config: &Config) -> Result<AsyncClient> {
+        AsyncClient::with_transport(
+            config,
+            Arc::new(AsyncReqwestTransport::new()?),
+        )
+        .await
+    }
+
+    pub async fn with_transport(
+        config: &Config,
+        transport: Arc<dyn AsyncTransport>,
+    ) -> Result<AsyncClient> {
+        let collections: AsyncSession =
+            AsyncSession::new(config, Product::Collection, transport.clone())?;
+
+        let mut client: AsyncClient = AsyncClient {
+            target_environment: collections.state.target_environment.clone(),
+            base_url: collections.state.base_url.clone(),
+            callback_host: collections.state.callback_host.clone(),
+            collections,
+            disbursements: None,
+            remittances: None,
+        };
+
+        client.authorize_collections().await?;
+
+        if config.disbursement.is_some() {
+            client.disbursements = Some(
+                AsyncDisbursementClient::with_transport(
+                    config,
+                    transport.clone(),
+                )
+                .await?,
+            );
+        }
+
+        if config.remittance.is_some() {
+            client.remittances = Some(
+                AsyncRemittanceClient::with_transport(config, transport)
+                    .await?,
+            );
+        }
+
+        Ok(client)
+    }
+
+    pub fn disbursements(&mut self) -> Result<&mut AsyncDisbursementClient> {
+        if let Some(disbursements) = self.disbursements.as_mut() {
+            Ok(disbursements)
+        } else {
+            Err(MomoError::Config(format!(
+                "no {} credentials configured",
+                Product::Disbursement
+            )))
+        }
+    }
+
+    pub fn remittances(&mut self) -> Result<&mut AsyncRemittanceClient> {
+        if let Some(remittances) = self.remittances.as_mut() {
+            Ok(remittances)
+        } else {
+            Err(MomoError::Config(format!(
+                "no {} credentials configured",
+                Product::Remittance
+            )))
+        }
+    }
+
+    pub async fn authorize_collections(&mut self) -> Result<&AsyncClient> {
+        self.collections.authorize().await?;
+
+        Ok(self)
+    }
+
+    pub async fn request_to_pay(
+        &mut self,
+        request: &PaymentRequest,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections
+            .execute(collection::submit_payment_request(
+                "v1_0/requesttopay",
+                "requesting to pay",
+                request,
+                cb_url,
+            )?)
+            .await
+    }
+
+    pub async fn request_to_pay_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transaction> {
+        let path: String = format!("v1_0/requesttopay/{}", reference_id);
+
+        self.collections
+            .execute(collection::get_transaction(&path, reference_id))
+            .await
+    }
+
+    pub async fn request_to_pay_delivery_notification(
+        &mut self,
+        reference_id: &Uuid,
+        message: &str,
+        language: Option<&str>,
+    ) -> Result<()> {
+        self.collections
+            .execute(collection::request_to_pay_delivery_notification(
+                reference_id,
+                message,
+                language,
+            )?)
+            .await
+    }
+
+    pub async fn request_to_withdraw(
+        &mut self,
+        request: &PaymentRequest,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid> {
+        let path: String = format!("{}/requesttowithdraw", version);
+
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections
+            .execute(collection::submit_payment_request(
+                &path,
+                "requesting to withdraw",
+                request,
+                cb_url,
+            )?)
+            .await
+    }
+
+    pub async fn request_to_withdraw_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transaction> {
+        let path: String = format!("v1_0/requesttowithdraw/{}", reference_id);
+
+        self.collections
+            .execute(collection::get_transaction(&path, reference_id))
+            .await
+    }
+
+    pub async fn create_invoice(
+        &mut self,
+        invoice: &Invoice,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections
+            .execute(collection::create_invoice(invoice, cb_url)?)
+            .await
+    }
+
+    pub async fn get_invoice_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<InvoiceDetails> {
+        self.collections
+            .execute(collection::get_invoice_status(reference_id))
+            .await
+    }
+
+    pub async fn cancel_invoice(
+        &mut self,
+        reference_id: &Uuid,
+        external_id: &str,
+    ) -> Result<()> {
+        self.collections
+            .execute(collection::cancel_invoice(reference_id, external_id)?)
+            .await
+    }
+
+    pub async fn create_pre_approval(
+        &mut self,
+        pre_approval: &PreApproval,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections
+            .execute(collection::create_pre_approval(pre_approval, cb_url)?)
+            .await
+    }
+
+    pub async fn get_pre_approval_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<PreApprovalDetails> {
+        self.collections
+            .execute(collection::get_pre_approval_status(reference_id))
+            .await
+    }
+
+    pub async fn get_approved_pre_approvals(
+        &mut self,
+        payer: &Party,
+    ) -> Result<Vec<ApprovedPreApproval>> {
+        self.collections
+            .execute(collection::get_approved_pre_approvals(payer))
+            .await
+    }
+
+    pub async fn cancel_pre_approval(
+        &mut self,
+        pre_approval_id: &str,
+    ) -> Result<()> {
+        self.collections
+            .execute(collection::cancel_pre_approval(pre_approval_id))
+            .await
+    }
+
+    pub async fn create_payment(
+        &mut self,
+        bill_payment: &BillPayment,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections
+            .execute(collection::create_payment(bill_payment, cb_url)?)
+            .await
+    }
+
+    pub async fn get_payment_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<BillPaymentDetails> {
+        self.collections
+            .execute(collection::get_payment_status(reference_id))
+            .await
+    }
+
+    pub async fn get_balance(&mut self) -> Result<Balance> {
+        self.collections
+            .execute(collection::get_balance_at("v1_0/account/balance"))
+            .await
+    }
+
+    pub async fn get_balance_in(
+        &mut self,
+        currency: Currency,
+    ) -> Result<Balance> {
+        let path: String = format!("v1_0/account/balance/{}", currency);
+
+        self.collections
+            .execute(collection::get_balance_at(&path))
+            .await
+    }
+
+    pub async fn validate_account_holder(
+        &mut self,
+        account_holder: &Party,
+    ) -> Result<bool> {
+        self.collections
+            .execute(collection::validate_account_holder(account_holder))
+            .await
+    }
+
+    pub async fn get_basic_user_info(
+        &mut self,
+        msisdn: &Msisdn,
+    ) -> Result<BasicUserInfo> {
+        self.collections
+            .execute(collection::get_basic_user_info(msisdn))
+            .await
+    }
+
+    pub async fn bc_authorize(
+        &mut self,
+        msisdn: &Msisdn,
+        scopes: &[Scope],
+        callback_url: Option<&Url>,
+    ) -> Result<ConsentRequest> {
+        self.collections
+            .execute(collection::bc_authorize(msisdn, scopes, callback_url)?)
+            .await
+    }
+
+    // NOTE: resolves once the account holder answered the consent prompt,
+    // fails with MomoError::Consent if they denied it or never answered
+    pub async fn consent_token(
+        &mut self,
+        consent_request: &ConsentRequest,
+    ) -> Result<ConsentToken> {
+        let mut consent_poller: ConsentPoller =
+            ConsentPoller::new(consent_request);
+
+        loop {
+            let consent_poll: ConsentPoll = self
+                .collections
+                .execute(collection::poll_consent_token(
+                    &consent_request.auth_req_id,
+                ))
+                .await?;
+
+            match consent_poller.step(consent_poll)? {
+                ConsentStep::Granted(consent_token) => {
+                    return Ok(consent_token)
+                }
+                ConsentStep::Wait(interval) => {
+                    debug!(
+                        "[mini-mtn-momo] consent pending, polling again in {}s",
+                        interval.as_secs()
+                    );
+
+                    tokio::time::delay_for(interval).await;
+                }
+            }
+        }
+    }
+
+    pub async fn get_user_info_with_consent(
+        &mut self,
+        consent_token: &ConsentToken,
+    ) -> Result<UserInfoWithConsent> {
+        self.collections
+            .execute(collection::get_user_info_with_consent(consent_token))
+            .await
+    }
+}
+
+impl AsyncDisbursementClient {
+    pub async fn new(config: &Config) -> Result<AsyncDisbursementClient> {
+        AsyncDisbursementClient::with_transport(
+            config,
+            Arc::new(AsyncReqwestTransport::new()?),
+        )
+        .await
+    }
+
+    pub async fn with_transport(
+        config: &Config,
+        transport: Arc<dyn AsyncTransport>,
+    ) -> Result<AsyncDisbursementClient> {
+        let disbursements: AsyncSession =
+            AsyncSession::new(config, Product::Disbursement, transport)?;
+
+        let mut client: AsyncDisbursementClient = AsyncDisbursementClient {
+            target_environment: disbursements.state.target_environment.clone(),
+            base_url: disbursements.state.base_url.clone(),
+            callback_host: disbursements.state.callback_host.clone(),
+            disbursements,
+        };
+
+        client.authorize_disbursements().await?;
+
+        Ok(client)
+    }
+
+    pub async fn authorize_disbursements(
+        &mut self,
+    ) -> Result<&AsyncDisbursementClient> {
+        self.disbursements.authorize().await?;
+
+        Ok(self)
+    }
+
+    pub async fn transfer(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        request.validate()?;
+
+        let cb_url: &Url = self.disbursements.callback_url(callback_url)?;
+
+        self.disbursements
+            .execute(disbursement::submit_transfer(
+                "v1_0/transfer",
+                "transferring",
+                request.reference_id,
+                request.to_json(),
+                cb_url,
+            ))
+            .await
+    }
+
+    pub async fn transfer_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transfer> {
+        let path: String = format!("v1_0/transfer/{}", reference_id);
+
+        self.disbursements
+            .execute(disbursement::get_transfer(
+                &path,
+                "requesting transfer status",
+                reference_id,
+            ))
+            .await
+    }
+
+    pub async fn deposit(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid> {
+        request.validate()?;
+
+        let path: String = format!("{}/deposit", version);
+
+        let cb_url: &Url = self.disbursements.callback_url(callback_url)?;
+
+        self.disbursements
+            .execute(disbursement::submit_transfer(
+                &path,
+                "depositing",
+                request.reference_id,
+                request.to_json(),
+                cb_url,
+            ))
+            .await
+    }
+
+    pub async fn deposit_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transfer> {
+        let path: String = format!("v1_0/deposit/{}", reference_id);
+
+        self.disbursements
+            .execute(disbursement::get_transfer(
+                &path,
+                "requesting deposit status",
+                reference_id,
+            ))
+            .await
+    }
+
+    pub async fn refund(
+        &mut self,
+        request: &RefundRequest,
+        original: &Transaction,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid> {
+        request.validate_against(original)?;
+
+        let path: String = format!("{}/refund", version);
+
+        let cb_url: &Url = self.disbursements.callback_url(callback_url)?;
+
+        self.disbursements
+            .execute(disbursement::submit_transfer(
+                &path,
+                "refunding",
+                request.reference_id,
+                request.to_json(original),
+                cb_url,
+            ))
+            .await
+    }
+
+    pub async fn refund_status(
+        &mut self,
+        reference_id: &Uuid,
+        reference_id_to_refund: &Uuid,
+    ) -> Result<Refund> {
+        self.disbursements
+            .execute(disbursement::get_refund(
+                reference_id,
+                reference_id_to_refund,
+            ))
+            .await
+    }
+
+    pub async fn get_balance(&mut self) -> Result<Balance> {
+        self.disbursements
+            .execute(disbursement::get_balance_at(
+                "v1_0/account/balance",
+                Product::Disbursement,
+            ))
+            .await
+    }
+}
+
+impl AsyncRemittanceClient {
+    pub async fn new(config: &Config) -> Result<AsyncRemittanceClient> {
+        AsyncRemittanceClient::with_transport(
+            config,
+            Arc::new(AsyncReqwestTransport::new()?),
+        )
+        .await
+    }
+
+    pub async fn with_transport(
+        config: &Config,
+        transport: Arc<dyn AsyncTransport>,
+    ) -> Result<AsyncRemittanceClient> {
+        let remittances: AsyncSession =
+            AsyncSession::new(config, Product::Remittance, transport)?;
+
+        let mut client: AsyncRemittanceClient = AsyncRemittanceClient {
+            target_environment: remittances.state.target_environment.clone(),
+            base_url: remittances.state.base_url.clone(),
+            callback_host: remittances.state.callback_host.clone(),
+            remittances,
+        };
+
+        client.authorize_remittances().await?;
+
+        Ok(client)
+    }
+
+    pub async fn authorize_remittances(
+        &mut self,
+    ) -> Result<&AsyncRemittanceClient> {
+        self.remittances.authorize().await?;
+
+        Ok(self)
+    }
+
+    pub async fn transfer(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        request.validate()?;
+
+        let cb_url: &Url = self.remittances.callback_url(callback_url)?;
+
+        self.remittances
+            .execute(disbursement::submit_transfer(
+                "v1_0/transfer",
+                "transferring",
+                request.reference_id,
+                request.to_json(),
+                cb_url,
+            ))
+            .await
+    }
+
+    pub async fn transfer_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transfer> {
+        let path: String = format!("v1_0/transfer/{}", reference_id);
+
+        self.remittances
+            .execute(disbursement::get_transfer(
+                &path,
+                "requesting transfer status",
+                reference_id,
+            ))
+            .await
+    }
+
+    pub async fn cash_transfer(
+        &mut self,
+        cash_transfer: &CashTransfer,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        cash_transfer.validate()?;
+
+        let cb_url: &Url = self.remittances.callback_url(callback_url)?;
+
+        self.remittances
+            .execute(disbursement::submit_transfer(
+                "v2_0/cashtransfer",
+                "sending cash transfer",
+                cash_transfer.reference_id,
+                cash_transfer.to_json(),
+                cb_url,
+            ))
+            .await
+    }
+
+    pub async fn cash_transfer_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<CashTransferDetails> {
+        let path: String = format!("v2_0/cashtransfer/{}", reference_id);
+
+        self.remittances
+            .execute(remittance::get_cash_transfer(&path, reference_id))
+            .await
+    }
+
+    pub async fn get_balance(&mut self) -> Result<Balance> {
+        self.remittances
+            .execute(disbursement::get_balance_at(
+                "v1_0/account/balance",
+                Product::Remittance,
+            ))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_async_client_unit_tests {
+    use super::*;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    // NOTE: axum and friends only accept handlers whose futures are Send
+    #[test]
+    fn async_client_futures_are_send() {
+        let config: Config = Config {
+            username: String::from("collection-user"),
+            password: String::from("collection-password"),
+            subscription_key: String::from("collection-key"),
+            base_url: None,
+            callback_host: None,
+            disbursement: None,
+            remittance: None,
+            retry: None,
+        };
+
+        assert_send(&AsyncClient::new(&config));
+
+        let mut client: AsyncClient = AsyncClient {
+            target_environment: String::from(SANDBOX),
+            base_url: String::from(SANDBOX_BASE_URL),
+            callback_host: String::from(FALLBACK_CALLBACK_HOST),
+            collections: AsyncSession::new(
+                &config,
+                Product::Collection,
+                Arc::new(AsyncReqwestTransport::new().expect("transport")),
+            )
+            .expect("async session"),
+            disbursements: None,
+            remittances: None,
+        };
+
+        assert_send(&client.get_balance());
+    }
+}
@@ -0,0 +1,170 @@
+use http::StatusCode;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use url::Url;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::transport::HttpResponse;
+
+// NOTE: how a request authenticates, most use the product's access token
+// which the session keeps fresh
+pub(crate) enum Auth {
+    Product,
+    Basic,
+    Token(String),
+}
+
+// NOTE: an MTN api request independent of the http client sending it, the
+// path is relative to the product, e.g. "v1_0/requesttopay"
+pub(crate) struct ApiRequest {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Option<String>,
+    pub(crate) auth: Auth,
+    pub(crate) idempotent: bool,
+}
+
+impl ApiRequest {
+    pub(crate) fn new(method: Method, path: &str) -> ApiRequest {
+        ApiRequest {
+            method,
+            path: path.to_string(),
+            headers: vec![],
+            body: None,
+            auth: Auth::Product,
+            idempotent: false,
+        }
+    }
+
+    pub(crate) fn header(mut self, name: &str, value: &str) -> ApiRequest {
+        self.headers.push((name.to_string(), value.to_string()));
+
+        self
+    }
+
+    pub(crate) fn body(mut self, body: String) -> ApiRequest {
+        self.body = Some(body);
+
+        self
+    }
+
+    pub(crate) fn auth(mut self, auth: Auth) -> ApiRequest {
+        self.auth = auth;
+
+        self
+    }
+
+    // NOTE: only idempotent requests are resent on transient failures
+    pub(crate) fn idempotent(mut self) -> ApiRequest {
+        self.idempotent = true;
+
+        self
+    }
+
+    // NOTE: bodies are json unless the request says otherwise
+    pub(crate) fn content_type(&self) -> &str {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+            .map(|(_, value)| value.as_str())
+            .unwrap_or("application/json")
+    }
+}
+
+// NOTE: what a call is about, reported along with its failure
+pub(crate) enum Context {
+    ReferenceId(Uuid),
+    Described(String),
+}
+
+type Parse<T> = Box<dyn FnOnce(HttpResponse) -> Result<T> + Send>;
+
+// NOTE: one api operation, built and parsed the same way whether the
+// blocking or the async client sends it
+pub(crate) struct Call<T> {
+    pub(crate) request: ApiRequest,
+    pub(crate) parse: Parse<T>,
+}
+
+impl<T: 'static> Call<T> {
+    pub(crate) fn new<F>(request: ApiRequest, parse: F) -> Call<T>
+    where
+        F: FnOnce(HttpResponse) -> Result<T> + Send + 'static,
+    {
+        Call {
+            request,
+            parse: Box::new(parse),
+        }
+    }
+
+    // NOTE: expects a json body with a 200, converting it with convert
+    pub(crate) fn ok_json<W, F>(
+        request: ApiRequest,
+        what: &str,
+        context: Option<Context>,
+        convert: F,
+    ) -> Call<T>
+    where
+        W: DeserializeOwned,
+        F: FnOnce(W) -> Result<T> + Send + 'static,
+    {
+        let what: String = what.to_string();
+
+        Call::new(request, move |response| {
+            if response.status == StatusCode::OK {
+                convert(response.json::<W>()?)
+            } else {
+                Err(response.failure(&what, context.as_ref()))
+            }
+        })
+    }
+}
+
+impl Call<()> {
+    pub(crate) fn ok(
+        request: ApiRequest,
+        what: &str,
+        context: Context,
+    ) -> Call<()> {
+        let what: String = what.to_string();
+
+        Call::new(request, move |response| {
+            if response.status == StatusCode::OK {
+                Ok(())
+            } else {
+                Err(response.failure(&what, Some(&context)))
+            }
+        })
+    }
+}
+
+impl Call<Uuid> {
+    // NOTE: MTN acknowledges asynchronously processed requests with a 202,
+    // their outcome is looked up or pushed to the callback url later
+    pub(crate) fn submit(
+        path: &str,
+        what: &str,
+        reference_id: Uuid,
+        callback_url: &Url,
+        body: String,
+    ) -> Call<Uuid> {
+        let request: ApiRequest = ApiRequest::new(Method::POST, path)
+            .header("X-Callback-Url", callback_url.as_str())
+            .header("X-Reference-Id", &reference_id.to_string())
+            .body(body);
+
+        let what: String = what.to_string();
+
+        Call::new(request, move |response| {
+            if response.status == StatusCode::ACCEPTED {
+                Ok(reference_id)
+            } else {
+                Err(response
+                    .failure(&what, Some(&Context::ReferenceId(reference_id))))
+            }
+        })
+    }
+}
@@ -0,0 +1,323 @@
+use http::StatusCode;
+use reqwest::Method;
+use serde_json::json;
+use url::Url;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::call::{ApiRequest, Auth, Call, Context};
+use crate::consent::{
+    self, BcAuthorizeResponse, ConsentPoll, ConsentRequest, ConsentToken,
+    OAuth2Error, Scope, UserInfoWithConsent,
+};
+use crate::{
+    party_json, validate_delivery_notification, validate_external_id,
+    AccountHolderStatus, ApprovedPreApproval, BasicUserInfo, BillPayment,
+    BillPaymentDetails, BillPaymentResponse, Invoice, InvoiceDetails,
+    InvoiceResponse, Payment, PaymentRequest, PreApproval, PreApprovalDetails,
+    PreApprovalResponse, Transaction,
+};
+
+// NOTE: the collection api calls shared by the blocking and the async
+// client, which only resolve callback urls and send them
+
+const FORM_URLENCODED: &str = "application/x-www-form-urlencoded";
+
+pub(crate) fn submit_payment_request(
+    path: &str,
+    what: &str,
+    request: &PaymentRequest,
+    callback_url: &Url,
+) -> Result<Call<Uuid>> {
+    request.validate()?;
+
+    let reference_id: Uuid = request.reference_id.unwrap_or_else(Uuid::new_v4);
+
+    let body: String = json!({
+        "amount": request.amount,
+        "currency": request.currency.to_string(),
+        "externalId": &request.external_id,
+        "payer": party_json(&request.payer),
+        "payerMessage": &request.payer_message,
+        "payeeNote": &request.payee_note,
+    })
+    .to_string();
+
+    Ok(Call::submit(path, what, reference_id, callback_url, body))
+}
+
+pub(crate) fn get_transaction(
+    path: &str,
+    reference_id: &Uuid,
+) -> Call<Transaction> {
+    let reference_id: Uuid = *reference_id;
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, path).idempotent(),
+        "requesting transaction status",
+        Some(Context::ReferenceId(reference_id)),
+        move |payment: Payment| payment.into_transaction(reference_id),
+    )
+}
+
+// NOTE: language is an ISO 639-1 code like "en" that MTN uses for the sms
+// it sends to the payer of the referenced request to pay
+pub(crate) fn request_to_pay_delivery_notification(
+    reference_id: &Uuid,
+    message: &str,
+    language: Option<&str>,
+) -> Result<Call<()>> {
+    validate_delivery_notification(message, language)?;
+
+    let path: String =
+        format!("v1_0/requesttopay/{}/deliverynotification", reference_id);
+
+    let mut request: ApiRequest = ApiRequest::new(Method::POST, &path)
+        .header("notificationMessage", message)
+        .body(json!({ "notificationMessage": message }).to_string());
+
+    if let Some(language) = language {
+        request = request.header("Language", language);
+    }
+
+    Ok(Call::ok(
+        request,
+        "sending delivery notification",
+        Context::ReferenceId(*reference_id),
+    ))
+}
+
+pub(crate) fn create_invoice(
+    invoice: &Invoice,
+    callback_url: &Url,
+) -> Result<Call<Uuid>> {
+    invoice.validate()?;
+
+    Ok(Call::submit(
+        "v2_0/invoice",
+        "creating invoice",
+        invoice.reference_id.unwrap_or_else(Uuid::new_v4),
+        callback_url,
+        invoice.to_json(),
+    ))
+}
+
+pub(crate) fn get_invoice_status(reference_id: &Uuid) -> Call<InvoiceDetails> {
+    let path: String = format!("v2_0/invoice/{}", reference_id);
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, &path).idempotent(),
+        "requesting invoice status",
+        Some(Context::ReferenceId(*reference_id)),
+        InvoiceResponse::into_invoice_details,
+    )
+}
+
+// NOTE: MTN wants the external id the invoice has been created with and a
+// fresh reference id for the cancellation itself
+pub(crate) fn cancel_invoice(
+    reference_id: &Uuid,
+    external_id: &str,
+) -> Result<Call<()>> {
+    validate_external_id(external_id)?;
+
+    let path: String = format!("v2_0/invoice/{}", reference_id);
+
+    let request: ApiRequest = ApiRequest::new(Method::DELETE, &path)
+        .header("X-Reference-Id", &Uuid::new_v4().to_string())
+        .body(json!({ "externalId": external_id }).to_string());
+
+    Ok(Call::ok(
+        request,
+        "cancelling invoice",
+        Context::ReferenceId(*reference_id),
+    ))
+}
+
+pub(crate) fn create_pre_approval(
+    pre_approval: &PreApproval,
+    callback_url: &Url,
+) -> Result<Call<Uuid>> {
+    pre_approval.validate()?;
+
+    Ok(Call::submit(
+        "v2_0/preapproval",
+        "creating pre-approval",
+        pre_approval.reference_id.unwrap_or_else(Uuid::new_v4),
+        callback_url,
+        pre_approval.to_json(),
+    ))
+}
+
+pub(crate) fn get_pre_approval_status(
+    reference_id: &Uuid,
+) -> Call<PreApprovalDetails> {
+    let path: String = format!("v2_0/preapproval/{}", reference_id);
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, &path).idempotent(),
+        "requesting pre-approval status",
+        Some(Context::ReferenceId(*reference_id)),
+        PreApprovalResponse::into_pre_approval_details,
+    )
+}
+
+pub(crate) fn get_approved_pre_approvals(
+    payer: &Party,
+) -> Call<Vec<ApprovedPreApproval>> {
+    let path: String = format!(
+        "v1_0/preapprovals/{}/{}",
+        payer.id_type().path_segment(),
+        payer
+    );
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, &path).idempotent(),
+        "listing approved pre-approvals",
+        Some(Context::Described(format!("account holder {}", payer))),
+        Ok,
+    )
+}
+
+pub(crate) fn cancel_pre_approval(pre_approval_id: &str) -> Call<()> {
+    let path: String = format!("v1_0/preapproval/{}", pre_approval_id);
+
+    Call::ok(
+        ApiRequest::new(Method::DELETE, &path),
+        "cancelling pre-approval",
+        Context::Described(format!("pre-approval id {}", pre_approval_id)),
+    )
+}
+
+pub(crate) fn create_payment(
+    bill_payment: &BillPayment,
+    callback_url: &Url,
+) -> Result<Call<Uuid>> {
+    bill_payment.validate()?;
+
+    Ok(Call::submit(
+        "v2_0/payment",
+        "creating payment",
+        bill_payment.reference_id.unwrap_or_else(Uuid::new_v4),
+        callback_url,
+        bill_payment.to_json(),
+    ))
+}
+
+pub(crate) fn get_payment_status(
+    reference_id: &Uuid,
+) -> Call<BillPaymentDetails> {
+    let path: String = format!("v2_0/payment/{}", reference_id);
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, &path).idempotent(),
+        "requesting payment status",
+        Some(Context::ReferenceId(*reference_id)),
+        BillPaymentResponse::into_bill_payment_details,
+    )
+}
+
+pub(crate) fn get_balance_at(path: &str) -> Call<Balance> {
+    Call::ok_json(
+        ApiRequest::new(Method::GET, path).idempotent(),
+        "getting wallet balance",
+        None,
+        Ok,
+    )
+}
+
+pub(crate) fn validate_account_holder(account_holder: &Party) -> Call<bool> {
+    let path: String = format!(
+        "v1_0/accountholder/{}/{}/active",
+        account_holder.id_type().path_segment(),
+        account_holder
+    );
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, &path).idempotent(),
+        "validating account holder",
+        Some(Context::Described(format!(
+            "account holder {}",
+            account_holder
+        ))),
+        |status: AccountHolderStatus| Ok(status.result),
+    )
+}
+
+pub(crate) fn get_basic_user_info(msisdn: &Msisdn) -> Call<BasicUserInfo> {
+    let path: String =
+        format!("v1_0/accountholder/msisdn/{}/basicuserinfo", msisdn);
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, &path).idempotent(),
+        "getting basic user info",
+        Some(Context::Described(format!("account holder {}", msisdn))),
+        Ok,
+    )
+}
+
+// NOTE: without a callback url the consent request is only polled
+pub(crate) fn bc_authorize(
+    msisdn: &Msisdn,
+    scopes: &[Scope],
+    callback_url: Option<&Url>,
+) -> Result<Call<ConsentRequest>> {
+    if scopes.is_empty() {
+        return Err(MomoError::Validation(String::from(
+            "consent requires at least one scope",
+        )));
+    }
+
+    let mut request: ApiRequest =
+        ApiRequest::new(Method::POST, "v1_0/bc-authorize")
+            .header("Content-Type", FORM_URLENCODED)
+            .body(consent::bc_authorize_form(msisdn, scopes));
+
+    if let Some(url) = callback_url {
+        request = request.header("X-Callback-Url", url.as_str());
+    }
+
+    Ok(Call::ok_json(
+        request,
+        "requesting consent",
+        Some(Context::Described(format!("account holder {}", msisdn))),
+        |response: BcAuthorizeResponse| Ok(response.into_consent_request()),
+    ))
+}
+
+pub(crate) fn poll_consent_token(auth_req_id: &str) -> Call<ConsentPoll> {
+    let request: ApiRequest = ApiRequest::new(Method::POST, "oauth2/token/")
+        .header("Content-Type", FORM_URLENCODED)
+        .body(consent::token_form(auth_req_id))
+        .auth(Auth::Basic);
+
+    Call::new(request, |response| {
+        if response.status == StatusCode::OK {
+            let consent_token: ConsentToken =
+                response.json::<ConsentToken>()?;
+
+            return Ok(ConsentPoll::Granted(consent_token));
+        }
+
+        // NOTE: a pending or denied consent comes back as an oauth2 error
+        if let Ok(oauth2_error) = response.json::<OAuth2Error>() {
+            if let Some(consent_poll) = oauth2_error.into_consent_poll() {
+                return Ok(consent_poll?);
+            }
+        }
+
+        Err(response.failure("polling consent token", None))
+    })
+}
+
+pub(crate) fn get_user_info_with_consent(
+    consent_token: &ConsentToken,
+) -> Call<UserInfoWithConsent> {
+    let request: ApiRequest =
+        ApiRequest::new(Method::GET, "oauth2/v1_0/userinfo")
+            .auth(Auth::Token(consent_token.access_token.clone()))
+            .idempotent();
+
+    Call::ok_json(request, "getting user info with consent", None, Ok)
+}
@@ -0,0 +1,266 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+
+use common::*;
+
+// NOTE: what an account holder is asked to share with us
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Scope {
+    OpenId,
+    Profile,
+    Email,
+    Phone,
+    Address,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s: &str = match self {
+            Scope::OpenId => "openid",
+            Scope::Profile => "profile",
+            Scope::Email => "email",
+            Scope::Phone => "phone",
+            Scope::Address => "address",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// NOTE: a consent prompt sent to the account holder's phone, poll for its
+// token no more often than every interval
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConsentRequest {
+    pub auth_req_id: String,
+    pub interval: Duration,
+    pub expires_in: Duration,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BcAuthorizeResponse {
+    auth_req_id: String,
+    interval: Option<u64>,
+    expires_in: u64,
+}
+
+impl BcAuthorizeResponse {
+    pub(crate) fn into_consent_request(self) -> ConsentRequest {
+        ConsentRequest {
+            auth_req_id: self.auth_req_id,
+            // NOTE: the ciba spec has clients wait 5s if no interval is given
+            interval: Duration::from_secs(self.interval.unwrap_or(5u64)),
+            expires_in: Duration::from_secs(self.expires_in),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConsentToken {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    pub scope: Option<String>,
+    pub refresh_token: Option<String>,
+}
+
+// NOTE: the outcome of a single token poll
+pub(crate) enum ConsentPoll {
+    Granted(ConsentToken),
+    Pending,
+    SlowDown,
+}
+
+pub(crate) enum ConsentStep {
+    Granted(ConsentToken),
+    Wait(Duration),
+}
+
+// NOTE: paces polling for a consent token, shared by the blocking and the
+// async client which only differ in how they wait
+pub(crate) struct ConsentPoller {
+    deadline: Instant,
+    interval: Duration,
+}
+
+impl ConsentPoller {
+    pub(crate) fn new(consent_request: &ConsentRequest) -> ConsentPoller {
+        ConsentPoller {
+            deadline: Instant::now() + consent_request.expires_in,
+            interval: consent_request.interval,
+        }
+    }
+
+    pub(crate) fn step(
+        &mut self,
+        consent_poll: ConsentPoll,
+    ) -> Result<ConsentStep, ConsentError> {
+        match consent_poll {
+            ConsentPoll::Granted(consent_token) => {
+                return Ok(ConsentStep::Granted(consent_token))
+            }
+            ConsentPoll::Pending => {}
+            ConsentPoll::SlowDown => self.interval += Duration::from_secs(5u64),
+        }
+
+        if Instant::now() + self.interval >= self.deadline {
+            Err(ConsentError::Expired)
+        } else {
+            Ok(ConsentStep::Wait(self.interval))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct OAuth2Error {
+    pub(crate) error: String,
+}
+
+impl OAuth2Error {
+    pub(crate) fn into_consent_poll(
+        self,
+    ) -> Option<Result<ConsentPoll, ConsentError>> {
+        match self.error.as_str() {
+            "authorization_pending" => Some(Ok(ConsentPoll::Pending)),
+            "slow_down" => Some(Ok(ConsentPoll::SlowDown)),
+            "access_denied" => Some(Err(ConsentError::Denied)),
+            "expired_token" => Some(Err(ConsentError::Expired)),
+            _ => None,
+        }
+    }
+}
+
+// NOTE: the verified know your customer data of an account holder who
+// consented, which fields are set depends on the granted scopes
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UserInfoWithConsent {
+    pub sub: Option<String>,
+    pub name: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub middle_name: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub gender: Option<String>,
+    pub locale: Option<String>,
+    pub phone_number: Option<String>,
+    pub phone_number_verified: Option<bool>,
+    pub address: Option<String>,
+    pub updated_at: Option<u64>,
+    pub status: Option<String>,
+    pub birthdate: Option<String>,
+    pub credit_score: Option<String>,
+    pub active: Option<bool>,
+    pub country_of_birth: Option<String>,
+    pub region_of_birth: Option<String>,
+    pub city_of_birth: Option<String>,
+    pub occupation: Option<String>,
+    pub employer_name: Option<String>,
+    pub identification_type: Option<String>,
+    pub identification_value: Option<String>,
+}
+
+pub(crate) fn bc_authorize_form(msisdn: &Msisdn, scopes: &[Scope]) -> String {
+    let scope: String = scopes
+        .iter()
+        .map(Scope::to_string)
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    form_urlencoded::Serializer::new(String::new())
+        .append_pair("login_hint", &format!("ID:{}/MSISDN", msisdn))
+        .append_pair("scope", &scope)
+        .append_pair("access_type", "offline")
+        .finish()
+}
+
+pub(crate) fn token_form(auth_req_id: &str) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .append_pair("grant_type", "urn:openid:params:grant-type:ciba")
+        .append_pair("auth_req_id", auth_req_id)
+        .finish()
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_consent_unit_tests {
+    use super::*;
+    use crate::GHANA;
+
+    #[test]
+    fn encoding_a_bc_authorize_form() {
+        let msisdn: Msisdn =
+            Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+        assert_eq!(
+            bc_authorize_form(&msisdn, &[Scope::OpenId, Scope::Profile]),
+            "login_hint=ID%3A233542373722%2FMSISDN&scope=openid+profile&\
+             access_type=offline"
+        );
+    }
+
+    #[test]
+    fn deserializing_a_bc_authorize_response() {
+        let json: &str = r#"{
+            "auth_req_id": "3c8e2a1f-0d4b-4b7c-9a61-1f2e3d4c5b6a",
+            "interval": 10,
+            "expires_in": 3600
+        }"#;
+
+        let consent_request: ConsentRequest =
+            serde_json::from_str::<BcAuthorizeResponse>(json)
+                .expect("bc-authorize response")
+                .into_consent_request();
+
+        assert_eq!(consent_request.interval, Duration::from_secs(10u64));
+        assert_eq!(consent_request.expires_in, Duration::from_secs(3600u64));
+    }
+
+    #[test]
+    fn mapping_oauth2_errors_to_consent_errors() {
+        let denied: OAuth2Error = OAuth2Error {
+            error: String::from("access_denied"),
+        };
+        let unknown: OAuth2Error = OAuth2Error {
+            error: String::from("invalid_grant"),
+        };
+
+        assert!(matches!(
+            denied.into_consent_poll(),
+            Some(Err(ConsentError::Denied))
+        ));
+        assert!(unknown.into_consent_poll().is_none());
+    }
+
+    #[test]
+    fn slowing_down_consent_polling() {
+        let mut consent_poller: ConsentPoller =
+            ConsentPoller::new(&ConsentRequest {
+                auth_req_id: String::from("auth-req-id"),
+                interval: Duration::from_secs(5u64),
+                expires_in: Duration::from_secs(60u64),
+            });
+
+        assert!(matches!(
+            consent_poller.step(ConsentPoll::SlowDown),
+            Ok(ConsentStep::Wait(interval))
+                if interval == Duration::from_secs(10u64)
+        ));
+    }
+
+    #[test]
+    fn expiring_consent_polling() {
+        let mut consent_poller: ConsentPoller =
+            ConsentPoller::new(&ConsentRequest {
+                auth_req_id: String::from("auth-req-id"),
+                interval: Duration::from_secs(5u64),
+                expires_in: Duration::from_secs(5u64),
+            });
+
+        assert!(matches!(
+            consent_poller.step(ConsentPoll::Pending),
+            Err(ConsentError::Expired)
+        ));
+    }
+}
@@ -0,0 +1,716 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use url::Url;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::call::{ApiRequest, Call, Context};
+use crate::session::Session;
+use crate::transport::{ReqwestTransport, Transport};
+use crate::{
+    party_json, validate_external_id, validate_message, ApiVersion, Config,
+    Payee, Product, Reason, Transaction,
+};
+
+#[derive(Debug)]
+pub struct DisbursementClient {
+    pub target_environment: String,
+    pub base_url: String,
+    pub callback_host: String,
+    disbursements: Session,
+}
+
+// NOTE: used for transfers as well as deposits
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TransferRequest {
+    pub amount: u64,
+    pub currency: Currency,
+    pub payee: Party,
+    // written to the payer's, i.e. our own, transaction history
+    pub payer_message: String,
+    // shown to the payee in their transaction history
+    pub payee_note: String,
+    // the caller's own identifier for this transfer, e.g. a settlement id
+    pub external_id: String,
+    // NOTE: sent as X-Reference-Id, a random one is generated if unset
+    pub reference_id: Option<Uuid>,
+}
+
+impl TransferRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "transfer amount must be greater than zero",
+            )));
+        }
+
+        validate_message("payer message", &self.payer_message)?;
+        validate_message("payee note", &self.payee_note)?;
+        validate_external_id(&self.external_id)
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        json!({
+            "amount": self.amount.to_string(),
+            "currency": self.currency.to_string(),
+            "externalId": &self.external_id,
+            "payee": party_json(&self.payee),
+            "payerMessage": &self.payer_message,
+            "payeeNote": &self.payee_note,
+        })
+        .to_string()
+    }
+}
+
+// NOTE: refunds a collection, the currency is always the one of the
+// original payment
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RefundRequest {
+    pub amount: u64,
+    // the reference id request_to_pay returned for the original payment
+    pub reference_id_to_refund: Uuid,
+    // written to the payer's, i.e. our own, transaction history
+    pub payer_message: String,
+    // shown to the original payer in their transaction history
+    pub payee_note: String,
+    // the caller's own identifier for this refund, e.g. a return id
+    pub external_id: String,
+    // NOTE: sent as X-Reference-Id, a random one is generated if unset
+    pub reference_id: Option<Uuid>,
+}
+
+impl RefundRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "refund amount must be greater than zero",
+            )));
+        }
+
+        validate_message("payer message", &self.payer_message)?;
+        validate_message("payee note", &self.payee_note)?;
+        validate_external_id(&self.external_id)
+    }
+
+    // NOTE: only successful payments can be refunded and never by more
+    // than was originally paid, original has to be the payment
+    // reference_id_to_refund names. The amount is checked for this refund
+    // alone, partial refunds made earlier are not subtracted
+    pub fn validate_against(&self, original: &Transaction) -> Result<()> {
+        self.validate()?;
+
+        if original.reference_id != self.reference_id_to_refund {
+            return Err(MomoError::Validation(format!(
+                "refund of payment {} validated against payment {}",
+                self.reference_id_to_refund, original.reference_id
+            )));
+        }
+
+        if original.status != PaymentStatus::Resolved {
+            return Err(MomoError::Validation(format!(
+                "payment {} cannot be refunded with status {}",
+                self.reference_id_to_refund, original.status
+            )));
+        }
+
+        let exceeds: Option<bool> =
+            parse_decimal(&original.amount).and_then(|(digits, scale)| {
+                let amount: u128 = u128::from(self.amount)
+                    .checked_mul(10u128.checked_pow(scale)?)?;
+
+                Some(amount > digits)
+            });
+
+        match exceeds {
+            Some(false) => Ok(()),
+            Some(true) => Err(MomoError::Validation(format!(
+                "refund amount {} exceeds amount {} of payment {}",
+                self.amount, original.amount, self.reference_id_to_refund
+            ))),
+            None => Err(MomoError::Validation(format!(
+                "amount {:?} of payment {} is not a decimal number",
+                original.amount, self.reference_id_to_refund
+            ))),
+        }
+    }
+
+    pub(crate) fn to_json(&self, original: &Transaction) -> String {
+        json!({
+            "amount": self.amount.to_string(),
+            "currency": &original.currency,
+            "externalId": &self.external_id,
+            "payerMessage": &self.payer_message,
+            "payeeNote": &self.payee_note,
+            "referenceIdToRefund": self.reference_id_to_refund.to_string(),
+        })
+        .to_string()
+    }
+}
+
+// NOTE: splits a decimal string like "100.50" into its digits without the
+// point and the number of them after it, i.e. (10050, 2)
+fn parse_decimal(amount: &str) -> Option<(u128, u32)> {
+    let (whole, fraction): (&str, &str) = match amount.find('.') {
+        Some(point) => (&amount[..point], &amount[point + 1usize..]),
+        None => (amount, ""),
+    };
+
+    if whole.is_empty()
+        || !whole.bytes().all(|byte| byte.is_ascii_digit())
+        || !fraction.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let digits: u128 = format!("{}{}", whole, fraction).parse::<u128>().ok()?;
+
+    Some((digits, fraction.len() as u32))
+}
+
+// NOTE: reported like a Transaction, with the payee in place of the payer
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Transfer {
+    // the reference id the transfer was looked up with
+    pub reference_id: Uuid,
+    pub amount: String,
+    pub currency: String,
+    pub financial_transaction_id: Option<String>,
+    pub external_id: Option<String>,
+    pub payee: Payee,
+    pub payer_message: Option<String>,
+    pub payee_note: Option<String>,
+    pub status: PaymentStatus,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub(crate) struct TransferResponse {
+    amount: String,
+    currency: String,
+    financialTransactionId: Option<String>,
+    externalId: Option<String>,
+    payee: Payee,
+    payerMessage: Option<String>,
+    payeeNote: Option<String>,
+    status: String,
+    reason: Option<Reason>,
+}
+
+impl TransferResponse {
+    pub(crate) fn into_transfer(self, reference_id: Uuid) -> Result<Transfer> {
+        let status: PaymentStatus = Reason::attach_to(
+            self.reason,
+            PaymentStatus::from_str(&self.status)?,
+        );
+
+        Ok(Transfer {
+            reference_id,
+            amount: self.amount,
+            currency: self.currency,
+            financial_transaction_id: self.financialTransactionId,
+            external_id: self.externalId,
+            payee: self.payee,
+            payer_message: self.payerMessage,
+            payee_note: self.payeeNote,
+            status,
+        })
+    }
+}
+
+// NOTE: a refund's status along with the payment it refunds
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Refund {
+    pub reference_id_to_refund: Uuid,
+    pub transfer: Transfer,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub(crate) struct RefundResponse {
+    #[serde(flatten)]
+    transfer: TransferResponse,
+    referenceIdToRefund: Option<Uuid>,
+}
+
+impl RefundResponse {
+    // NOTE: MTN does not always echo referenceIdToRefund, when it does it
+    // has to name the payment the caller expects to be refunded
+    pub(crate) fn into_refund(
+        self,
+        reference_id: Uuid,
+        reference_id_to_refund: Uuid,
+    ) -> Result<Refund> {
+        if let Some(refunded) = self.referenceIdToRefund {
+            if refunded != reference_id_to_refund {
+                return Err(MomoError::Validation(format!(
+                    "refund {} refunds payment {}, not payment {}",
+                    reference_id, refunded, reference_id_to_refund
+                )));
+            }
+        }
+
+        Ok(Refund {
+            reference_id_to_refund,
+            transfer: self.transfer.into_transfer(reference_id)?,
+        })
+    }
+}
+
+#[allow(clippy::new_ret_no_self)]
+pub trait IDisbursementClient {
+    fn new(config: &Config) -> Result<DisbursementClient>;
+    fn with_transport(
+        config: &Config,
+        transport: Arc<dyn Transport>,
+    ) -> Result<DisbursementClient>;
+    fn authorize_disbursements(&mut self) -> Result<&DisbursementClient>;
+    fn transfer(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid>;
+    fn transfer_status(&mut self, reference_id: &Uuid) -> Result<Transfer>;
+    fn deposit(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid>;
+    fn deposit_status(&mut self, reference_id: &Uuid) -> Result<Transfer>;
+    fn refund(
+        &mut self,
+        request: &RefundRequest,
+        original: &Transaction,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid>;
+    fn refund_status(
+        &mut self,
+        reference_id: &Uuid,
+        reference_id_to_refund: &Uuid,
+    ) -> Result<Refund>;
+    fn get_balance(&mut self) -> Result<Balance>;
+}
+
+impl IDisbursementClient for DisbursementClient {
+    fn new(config: &Config) -> Result<DisbursementClient> {
+        DisbursementClient::with_transport(
+            config,
+            Arc::new(ReqwestTransport::new()?),
+        )
+    }
+
+    fn with_transport(
+        config: &Config,
+        transport: Arc<dyn Transport>,
+    ) -> Result<DisbursementClient> {
+        let disbursements: Session =
+            Session::new(config, Product::Disbursement, transport)?;
+
+        let mut client: DisbursementClient = DisbursementClient {
+            target_environment: disbursements.state.target_environment.clone(),
+            base_url: disbursements.state.base_url.clone(),
+            callback_host: disbursements.state.callback_host.clone(),
+            disbursements,
+        };
+
+        client.authorize_disbursements()?;
+
+        Ok(client)
+    }
+
+    fn authorize_disbursements(&mut self) -> Result<&DisbursementClient> {
+        self.disbursements.authorize()?;
+
+        Ok(self)
+    }
+
+    fn transfer(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        request.validate()?;
+
+        let cb_url: &Url = self.disbursements.callback_url(callback_url)?;
+
+        self.disbursements.execute(submit_transfer(
+            "v1_0/transfer",
+            "transferring",
+            request.reference_id,
+            request.to_json(),
+            cb_url,
+        ))
+    }
+
+    fn transfer_status(&mut self, reference_id: &Uuid) -> Result<Transfer> {
+        let path: String = format!("v1_0/transfer/{}", reference_id);
+
+        self.disbursements.execute(get_transfer(
+            &path,
+            "requesting transfer status",
+            reference_id,
+        ))
+    }
+
+    fn deposit(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid> {
+        request.validate()?;
+
+        let path: String = format!("{}/deposit", version);
+
+        let cb_url: &Url = self.disbursements.callback_url(callback_url)?;
+
+        self.disbursements.execute(submit_transfer(
+            &path,
+            "depositing",
+            request.reference_id,
+            request.to_json(),
+            cb_url,
+        ))
+    }
+
+    fn deposit_status(&mut self, reference_id: &Uuid) -> Result<Transfer> {
+        let path: String = format!("v1_0/deposit/{}", reference_id);
+
+        self.disbursements.execute(get_transfer(
+            &path,
+            "requesting deposit status",
+            reference_id,
+        ))
+    }
+
+    fn refund(
+        &mut self,
+        request: &RefundRequest,
+        original: &Transaction,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid> {
+        request.validate_against(original)?;
+
+        let path: String = format!("{}/refund", version);
+
+        let cb_url: &Url = self.disbursements.callback_url(callback_url)?;
+
+        self.disbursements.execute(submit_transfer(
+            &path,
+            "refunding",
+            request.reference_id,
+            request.to_json(original),
+            cb_url,
+        ))
+    }
+
+    fn refund_status(
+        &mut self,
+        reference_id: &Uuid,
+        reference_id_to_refund: &Uuid,
+    ) -> Result<Refund> {
+        self.disbursements
+            .execute(get_refund(reference_id, reference_id_to_refund))
+    }
+
+    fn get_balance(&mut self) -> Result<Balance> {
+        self.disbursements.execute(get_balance_at(
+            "v1_0/account/balance",
+            Product::Disbursement,
+        ))
+    }
+}
+
+// NOTE: shared with the remittance client which uses the same transfer
+// resources under its own product path
+pub(crate) fn submit_transfer(
+    path: &str,
+    what: &str,
+    reference_id: Option<Uuid>,
+    body: String,
+    callback_url: &Url,
+) -> Call<Uuid> {
+    let reference_id: Uuid = reference_id.unwrap_or_else(Uuid::new_v4);
+
+    Call::submit(path, what, reference_id, callback_url, body)
+}
+
+pub(crate) fn get_transfer(
+    path: &str,
+    what: &str,
+    reference_id: &Uuid,
+) -> Call<Transfer> {
+    let reference_id: Uuid = *reference_id;
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, path).idempotent(),
+        what,
+        Some(Context::ReferenceId(reference_id)),
+        move |response: TransferResponse| response.into_transfer(reference_id),
+    )
+}
+
+pub(crate) fn get_refund(
+    reference_id: &Uuid,
+    reference_id_to_refund: &Uuid,
+) -> Call<Refund> {
+    let path: String = format!("v1_0/refund/{}", reference_id);
+    let reference_id: Uuid = *reference_id;
+    let reference_id_to_refund: Uuid = *reference_id_to_refund;
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, &path).idempotent(),
+        "requesting refund status",
+        Some(Context::ReferenceId(reference_id)),
+        move |response: RefundResponse| {
+            response.into_refund(reference_id, reference_id_to_refund)
+        },
+    )
+}
+
+pub(crate) fn get_balance_at(path: &str, product: Product) -> Call<Balance> {
+    Call::ok_json(
+        ApiRequest::new(Method::GET, path).idempotent(),
+        &format!("getting {} balance", product),
+        None,
+        Ok,
+    )
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_disbursement_unit_tests {
+    use super::*;
+    use crate::{Payer, GHANA};
+
+    fn transfer_request() -> TransferRequest {
+        TransferRequest {
+            amount: 419u64,
+            currency: Currency::Cedi,
+            payee: Party::from(
+                Msisdn::parse("0542373722", &GHANA, None).expect("msisdn"),
+            ),
+            payer_message: String::from("refund order 123"),
+            payee_note: String::from("Refund for order #123"),
+            external_id: String::from("refund-123"),
+            reference_id: None,
+        }
+    }
+
+    fn refund_request() -> RefundRequest {
+        RefundRequest {
+            amount: 419u64,
+            reference_id_to_refund: Uuid::parse_str(
+                "f5b8e1a4-2d4c-4d55-9b4a-2c1b3f2a0e11",
+            )
+            .expect("uuid"),
+            payer_message: String::from("refund order 123"),
+            payee_note: String::from("Refund for order #123"),
+            external_id: String::from("refund-123"),
+            reference_id: None,
+        }
+    }
+
+    fn original_payment(amount: &str, status: PaymentStatus) -> Transaction {
+        Transaction {
+            reference_id: refund_request().reference_id_to_refund,
+            amount: String::from(amount),
+            currency: String::from("EUR"),
+            financial_transaction_id: Some(String::from("1837462849")),
+            external_id: Some(String::from("order-123")),
+            payer: Payer {
+                party_id_type: PartyIdType::Msisdn,
+                party_id: String::from("233542373722"),
+            },
+            payer_message: None,
+            payee_note: None,
+            status,
+        }
+    }
+
+    #[test]
+    fn failing_without_disbursement_credentials() {
+        let config: Config = Config {
+            username: String::from("collection-user"),
+            password: String::from("collection-password"),
+            subscription_key: String::from("collection-key"),
+            base_url: None,
+            callback_host: None,
+            disbursement: None,
+            remittance: None,
+            retry: None,
+        };
+
+        match DisbursementClient::new(&config) {
+            Err(MomoError::Config(_)) => {}
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn transfer_request_validates() {
+        assert!(transfer_request().validate().is_ok());
+    }
+
+    #[test]
+    fn transfer_request_fails_on_zero_amount() {
+        let request: TransferRequest = TransferRequest {
+            amount: 0u64,
+            ..transfer_request()
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn transfer_request_fails_on_invalid_external_id() {
+        let request: TransferRequest = TransferRequest {
+            external_id: String::from("refund 123"),
+            ..transfer_request()
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn serializing_a_transfer_request() {
+        let json: serde_json::Value =
+            serde_json::from_str(&transfer_request().to_json()).expect("json");
+
+        assert_eq!(json["amount"], "419");
+        assert_eq!(json["payee"]["partyIdType"], "MSISDN");
+        assert_eq!(json["payee"]["partyId"], "233542373722");
+    }
+
+    #[test]
+    fn refund_request_validates_against_the_original_payment() {
+        let original: Transaction =
+            original_payment("419", PaymentStatus::Resolved);
+
+        assert!(refund_request().validate_against(&original).is_ok());
+    }
+
+    #[test]
+    fn refund_request_fails_on_exceeding_the_original_amount() {
+        let original: Transaction =
+            original_payment("418", PaymentStatus::Resolved);
+
+        assert!(refund_request().validate_against(&original).is_err());
+    }
+
+    #[test]
+    fn refund_request_validates_against_a_decimal_amount() {
+        let original: Transaction =
+            original_payment("419.50", PaymentStatus::Resolved);
+
+        assert!(refund_request().validate_against(&original).is_ok());
+        assert!(RefundRequest {
+            amount: 420u64,
+            ..refund_request()
+        }
+        .validate_against(&original)
+        .is_err());
+    }
+
+    #[test]
+    fn refund_request_fails_on_a_malformed_original_amount() {
+        for amount in &["", ".5", "4,19", "-419", "419.5.0"] {
+            let original: Transaction =
+                original_payment(amount, PaymentStatus::Resolved);
+
+            assert!(refund_request().validate_against(&original).is_err());
+        }
+    }
+
+    #[test]
+    fn refund_request_fails_on_an_unsuccessful_payment() {
+        let original: Transaction =
+            original_payment("419", PaymentStatus::Pending);
+
+        assert!(refund_request().validate_against(&original).is_err());
+    }
+
+    #[test]
+    fn refund_request_fails_on_another_payment() {
+        let original: Transaction = Transaction {
+            reference_id: Uuid::new_v4(),
+            ..original_payment("419", PaymentStatus::Resolved)
+        };
+
+        assert!(refund_request().validate_against(&original).is_err());
+    }
+
+    #[test]
+    fn serializing_a_refund_request() {
+        let original: Transaction =
+            original_payment("419", PaymentStatus::Resolved);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&refund_request().to_json(&original))
+                .expect("json");
+
+        assert_eq!(json["currency"], "EUR");
+        assert_eq!(
+            json["referenceIdToRefund"],
+            "f5b8e1a4-2d4c-4d55-9b4a-2c1b3f2a0e11"
+        );
+    }
+
+    #[test]
+    fn deserializing_a_refund_checks_the_refunded_payment() {
+        let json: &str = r#"{
+            "amount": "19",
+            "currency": "EUR",
+            "externalId": "refund-123",
+            "payee": { "partyIdType": "MSISDN", "partyId": "233542373722" },
+            "status": "SUCCESSFUL",
+            "referenceIdToRefund": "f5b8e1a4-2d4c-4d55-9b4a-2c1b3f2a0e11"
+        }"#;
+
+        let reference_id_to_refund: Uuid =
+            refund_request().reference_id_to_refund;
+
+        let refund: Refund = serde_json::from_str::<RefundResponse>(json)
+            .expect("refund response")
+            .into_refund(Uuid::new_v4(), reference_id_to_refund)
+            .expect("refund");
+
+        assert_eq!(refund.reference_id_to_refund, reference_id_to_refund);
+        assert_eq!(refund.transfer.status, PaymentStatus::Resolved);
+        assert!(serde_json::from_str::<RefundResponse>(json)
+            .expect("refund response")
+            .into_refund(Uuid::new_v4(), Uuid::new_v4())
+            .is_err());
+    }
+
+    #[test]
+    fn deserializing_a_successful_transfer() {
+        let json: &str = r#"{
+            "amount": "419",
+            "currency": "EUR",
+            "financialTransactionId": "1837462849",
+            "externalId": "refund-123",
+            "payee": { "partyIdType": "MSISDN", "partyId": "233542373722" },
+            "payerMessage": "refund order 123",
+            "payeeNote": "Refund for order #123",
+            "status": "SUCCESSFUL"
+        }"#;
+
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let transfer: Transfer = serde_json::from_str::<TransferResponse>(json)
+            .expect("transfer response")
+            .into_transfer(reference_id)
+            .expect("transfer");
+
+        assert_eq!(transfer.reference_id, reference_id);
+        assert_eq!(transfer.payee.party_id, "233542373722");
+        assert_eq!(transfer.status, PaymentStatus::Resolved);
+    }
+}
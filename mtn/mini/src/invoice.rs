@@ -0,0 +1,227 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::{
+    party_json, validate_external_id, validate_message, Payer, Reason,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Invoice {
+    pub amount: u64,
+    pub currency: Currency,
+    pub intended_payer: Party,
+    pub payee: Party,
+    // how long the intended payer has to settle the invoice
+    pub validity_duration: Duration,
+    pub description: String,
+    // the caller's own identifier for this invoice, e.g. an order id
+    pub external_id: String,
+    // NOTE: sent as X-Reference-Id, a random one is generated if unset
+    pub reference_id: Option<Uuid>,
+}
+
+impl Invoice {
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "invoice amount must be greater than zero",
+            )));
+        }
+
+        // NOTE: MTN takes the validity duration in whole seconds
+        if self.validity_duration.as_secs() == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "invoice validity duration must be at least one second",
+            )));
+        }
+
+        validate_message("invoice description", &self.description)?;
+        validate_external_id(&self.external_id)
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        json!({
+            "externalId": &self.external_id,
+            "amount": self.amount.to_string(),
+            "currency": self.currency.to_string(),
+            "validityDuration": self.validity_duration.as_secs().to_string(),
+            "intendedPayer": party_json(&self.intended_payer),
+            "payee": party_json(&self.payee),
+            "description": &self.description,
+        })
+        .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InvoiceDetails {
+    pub reference_id: String,
+    pub external_id: Option<String>,
+    pub amount: String,
+    pub currency: String,
+    pub status: PaymentStatus,
+    pub payment_reference: Option<String>,
+    pub invoice_id: Option<String>,
+    pub expiry_date_time: Option<String>,
+    pub payee_first_name: Option<String>,
+    pub payee_last_name: Option<String>,
+    pub intended_payer: Option<Payer>,
+    pub description: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub(crate) struct InvoiceResponse {
+    referenceId: String,
+    externalId: Option<String>,
+    amount: String,
+    currency: String,
+    status: String,
+    paymentReference: Option<String>,
+    invoiceId: Option<String>,
+    expiryDateTime: Option<String>,
+    payeeFirstName: Option<String>,
+    payeeLastName: Option<String>,
+    errorReason: Option<Reason>,
+    intendedPayer: Option<Payer>,
+    description: Option<String>,
+}
+
+impl InvoiceResponse {
+    pub(crate) fn into_invoice_details(self) -> Result<InvoiceDetails> {
+        let status: PaymentStatus = Reason::attach_to(
+            self.errorReason,
+            PaymentStatus::from_str(&self.status)?,
+        );
+
+        Ok(InvoiceDetails {
+            reference_id: self.referenceId,
+            external_id: self.externalId,
+            amount: self.amount,
+            currency: self.currency,
+            status,
+            payment_reference: self.paymentReference,
+            invoice_id: self.invoiceId,
+            expiry_date_time: self.expiryDateTime,
+            payee_first_name: self.payeeFirstName,
+            payee_last_name: self.payeeLastName,
+            intended_payer: self.intendedPayer,
+            description: self.description,
+        })
+    }
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_invoice_unit_tests {
+    use url::Url;
+
+    use super::*;
+    use crate::call::Call;
+    use crate::collection;
+    use crate::GHANA;
+
+    fn invoice() -> Invoice {
+        Invoice {
+            amount: 419u64,
+            currency: Currency::Cedi,
+            intended_payer: Party::from(
+                Msisdn::parse("0542373722", &GHANA, None).expect("msisdn"),
+            ),
+            payee: Party::from(
+                PartyCode::parse("NUGGET_DIGITAL").expect("party code"),
+            ),
+            validity_duration: Duration::from_secs(3600u64),
+            description: String::from("Invoice 123 for March"),
+            external_id: String::from("invoice-123"),
+            reference_id: None,
+        }
+    }
+
+    #[test]
+    fn invoice_validates() {
+        assert!(invoice().validate().is_ok());
+    }
+
+    #[test]
+    fn invoice_fails_on_zero_validity_duration() {
+        let invoice: Invoice = Invoice {
+            validity_duration: Duration::from_millis(999u64),
+            ..invoice()
+        };
+
+        assert!(invoice.validate().is_err());
+    }
+
+    #[test]
+    fn invoice_fails_on_zero_amount() {
+        let invoice: Invoice = Invoice {
+            amount: 0u64,
+            ..invoice()
+        };
+
+        assert!(invoice.validate().is_err());
+    }
+
+    #[test]
+    fn serializing_an_invoice() {
+        let json: serde_json::Value =
+            serde_json::from_str(&invoice().to_json()).expect("json");
+
+        assert_eq!(json["validityDuration"], "3600");
+        assert_eq!(json["intendedPayer"]["partyId"], "233542373722");
+        assert_eq!(json["payee"]["partyIdType"], "PARTY_CODE");
+        assert_eq!(json["payee"]["partyId"], "NUGGET_DIGITAL");
+    }
+
+    #[test]
+    fn deserializing_a_failed_invoice() {
+        let json: &str = r#"{
+            "referenceId": "f5b8e1a4-2d4c-4d55-9b4a-2c1b3f2a0e11",
+            "externalId": "invoice-123",
+            "amount": "419",
+            "currency": "EUR",
+            "status": "FAILED",
+            "paymentReference": "ABC123",
+            "invoiceId": "1234",
+            "expiryDateTime": "2020-06-01T12:00:00.000",
+            "errorReason": { "code": "EXPIRED", "message": "expired" }
+        }"#;
+
+        let invoice_details: InvoiceDetails =
+            serde_json::from_str::<InvoiceResponse>(json)
+                .expect("invoice response")
+                .into_invoice_details()
+                .expect("invoice details");
+
+        assert_eq!(invoice_details.invoice_id.as_deref(), Some("1234"));
+        assert_eq!(
+            invoice_details.status,
+            PaymentStatus::Rejected(Some(FailureReason::Expired))
+        );
+    }
+
+    #[test]
+    fn sending_the_caller_supplied_reference_id() {
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let call: Call<Uuid> = collection::create_invoice(
+            &Invoice {
+                reference_id: Some(reference_id),
+                ..invoice()
+            },
+            &Url::parse("https://nugget.digital/momo").expect("url"),
+        )
+        .expect("create_invoice");
+
+        assert!(call.request.headers.contains(&(
+            String::from("X-Reference-Id"),
+            reference_id.to_string()
+        )));
+    }
+}
@@ -1,9 +1,12 @@
+use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
-use anyhow::{bail, Result};
-use http::StatusCode;
+use lazy_static::lazy_static;
 use log::debug;
-use reqwest::blocking;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use url::Url;
@@ -11,319 +14,946 @@ use uuid::Uuid;
 
 use common::*;
 
+mod async_client;
+mod call;
+mod collection;
+mod consent;
+mod disbursement;
+mod invoice;
+mod payment;
+mod preapproval;
+mod remittance;
+mod retry;
+mod session;
+mod transport;
+pub use async_client::*;
+use consent::{ConsentPoll, ConsentPoller, ConsentStep};
+pub use consent::{ConsentRequest, ConsentToken, Scope, UserInfoWithConsent};
+pub use disbursement::*;
+pub use invoice::*;
+pub use payment::*;
+pub use preapproval::*;
+pub use remittance::*;
+pub use retry::RetryPolicy;
+use session::Session;
+pub use transport::{
+    AsyncReqwestTransport, AsyncTransport, HttpRequest, HttpResponse,
+    ReqwestTransport, Transport, TransportFuture,
+};
+
+// NOTE: the payer message ends up in an sms-sized approval prompt on the
+// payer's handset, quotes and markup get rejected by MTN
+pub const MAX_MESSAGE_LENGTH: usize = 160usize;
+pub const MAX_EXTERNAL_ID_LENGTH: usize = 64usize;
+// NOTE: access tokens get refreshed this long before they expire to not
+// have them expire while a request is in flight
+pub const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60u64);
+
+lazy_static! {
+    static ref MESSAGE_CHARACTERS: Regex =
+        Regex::new(r"^[A-Za-z0-9 .,:;!?#()/_+\-]*$").expect("regex");
+    static ref LANGUAGE_CODE: Regex = Regex::new("^[a-z]{2}$").expect("regex");
+    static ref EXTERNAL_ID_CHARACTERS: Regex =
+        Regex::new(r"^[A-Za-z0-9._\-]+$").expect("regex");
+    // NOTE: must never change, otherwise ids derived from the same
+    // external id before and after the change would differ
+    pub static ref REFERENCE_ID_NAMESPACE: Uuid = Uuid::new_v5(
+        &Uuid::NAMESPACE_URL,
+        b"https://momodeveloper.mtn.com/reference-id"
+    );
+}
+
+#[cfg(test)]
+lazy_static! {
+    // NOTE: the countries the unit tests parse msisdns for
+    pub(crate) static ref GHANA: Country = Country {
+        code: String::from("GH"),
+        prefix: String::from("233"),
+        non_prefix_digits: 9usize,
+    };
+    pub(crate) static ref NIGERIA: Country = Country {
+        code: String::from("NG"),
+        prefix: String::from("234"),
+        non_prefix_digits: 10usize,
+    };
+}
+
+// NOTE: derives a stable reference id (uuid v5) from an external id, e.g.
+// an order id, so that every retry of the same order reuses the same id
+pub fn reference_id_for(external_id: &str) -> Uuid {
+    Uuid::new_v5(&REFERENCE_ID_NAMESPACE, external_id.as_bytes())
+}
+
+// NOTE: the MTN apis, each with its own api user and subscription key
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Product {
+    Collection,
+    Disbursement,
+    Remittance,
+}
+
+impl fmt::Display for Product {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s: &str = match self {
+            Product::Collection => "collection",
+            Product::Disbursement => "disbursement",
+            Product::Remittance => "remittance",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// NOTE: MTN issues an api user and a subscription key per product
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+    pub subscription_key: String,
+}
+
 #[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Config {
+    // the collection credentials
     pub username: String,
     pub password: String,
     pub subscription_key: String,
     pub base_url: Option<String>,
     pub callback_host: Option<String>,
+    // NOTE: Client only serves disbursements and remittances when given
+    // their credentials
+    pub disbursement: Option<Credentials>,
+    pub remittance: Option<Credentials>,
+    // NOTE: falls back to RetryPolicy::default(), pass RetryPolicy::none()
+    // to fail on the first transient error
+    pub retry: Option<RetryPolicy>,
+}
+
+impl Config {
+    // NOTE: never falls back to the collection credentials, MTN would
+    // reject them for any other product with a 401
+    pub(crate) fn credentials(&self, product: Product) -> Result<Credentials> {
+        let credentials: Option<Credentials> = match product {
+            Product::Collection => Some(Credentials {
+                username: self.username.clone(),
+                password: self.password.clone(),
+                subscription_key: self.subscription_key.clone(),
+            }),
+            Product::Disbursement => self.disbursement.clone(),
+            Product::Remittance => self.remittance.clone(),
+        };
+
+        credentials.ok_or_else(|| {
+            MomoError::Config(format!("no {} credentials configured", product))
+        })
+    }
 }
 
 #[derive(Debug)]
 pub struct Client {
-    http_client: blocking::Client,
     pub target_environment: String,
-    username: String,
-    password: String,
-    subscription_key: String,
-    collections_access_token: String,
     pub base_url: String,
     pub callback_host: String,
-    reauthorize: bool,
+    collections: Session,
+    disbursements: Option<DisbursementClient>,
+    remittances: Option<RemittanceClient>,
 }
 
-#[derive(Deserialize)]
-struct Authorization {
-    access_token: String,
-    token_type: String,
-    expires_in: u64,
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ApiVersion {
+    V1,
+    V2,
 }
 
-#[allow(non_snake_case)]
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s: &str = match self {
+            ApiVersion::V1 => "v1_0",
+            ApiVersion::V2 => "v2_0",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// NOTE: used for requests to pay as well as requests to withdraw
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    pub amount: u64,
+    pub currency: Currency,
+    pub payer: Party,
+    // shown to the payer on the approval prompt and in their history
+    pub payer_message: String,
+    // written to the payee's transaction history
+    pub payee_note: String,
+    // the caller's own identifier for this payment, e.g. an order id
+    pub external_id: String,
+    // NOTE: sent as X-Reference-Id, a random one is generated if unset,
+    // MTN refuses a second request with the same reference id so passing
+    // reference_id_for(&external_id) makes retrying an order safe
+    pub reference_id: Option<Uuid>,
+}
+
+impl PaymentRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "payment request amount must be greater than zero",
+            )));
+        }
+
+        validate_message("payer message", &self.payer_message)?;
+        validate_message("payee note", &self.payee_note)?;
+        validate_external_id(&self.external_id)
+    }
+}
+
+pub(crate) fn party_json(party: &Party) -> serde_json::Value {
+    json!({
+      "partyIdType": party.id_type().to_string(),
+      "partyId": party.to_string(),
+    })
+}
+
+pub(crate) fn validate_external_id(external_id: &str) -> Result<()> {
+    if external_id.chars().count() > MAX_EXTERNAL_ID_LENGTH {
+        return Err(MomoError::Validation(format!(
+            "external id {:?} exceeds {} characters",
+            external_id, MAX_EXTERNAL_ID_LENGTH
+        )));
+    } else if !EXTERNAL_ID_CHARACTERS.is_match(external_id) {
+        return Err(MomoError::Validation(format!(
+            "external id {:?} must be non-empty and contain only \
+             ascii letters, digits, dots, dashes or underscores",
+            external_id
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_message(field: &str, message: &str) -> Result<()> {
+    if message.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(MomoError::Validation(format!(
+            "{} {:?} exceeds {} characters",
+            field, message, MAX_MESSAGE_LENGTH
+        )));
+    } else if !MESSAGE_CHARACTERS.is_match(message) {
+        return Err(MomoError::Validation(format!(
+            "{} {:?} contains unsupported characters",
+            field, message
+        )));
+    }
+
+    Ok(())
+}
+
+fn validate_delivery_notification(
+    message: &str,
+    language: Option<&str>,
+) -> Result<()> {
+    if message.is_empty() {
+        return Err(MomoError::Validation(String::from(
+            "delivery notification message must not be empty",
+        )));
+    }
+
+    validate_message("delivery notification message", message)?;
+
+    if let Some(language) = language {
+        if !LANGUAGE_CODE.is_match(language) {
+            return Err(MomoError::Validation(format!(
+                "delivery notification language {:?} is not an \
+                 ISO 639-1 code",
+                language
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Payer {
+    #[serde(rename = "partyIdType")]
+    pub party_id_type: PartyIdType,
+    #[serde(rename = "partyId")]
+    pub party_id: String,
+}
+
+// NOTE: MTN reports the receiving party the same way as the paying one
+pub type Payee = Payer;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    // the reference id the payment was requested with
+    pub reference_id: Uuid,
+    // NOTE: MTN reports amounts as decimal strings, e.g. "419"
+    pub amount: String,
+    pub currency: String,
+    // only assigned once the payment has been processed by MTN
+    pub financial_transaction_id: Option<String>,
+    pub external_id: Option<String>,
+    pub payer: Payer,
+    pub payer_message: Option<String>,
+    pub payee_note: Option<String>,
+    // NOTE: carries MTN's reason when Rejected or Declined, the status of
+    // every other lookup is filled the same way, see Reason::attach_to
+    pub status: PaymentStatus,
+}
+
+// NOTE: depending on the api version MTN sends the reason either as a
+// bare code or as an object carrying a code and a message
 #[derive(Deserialize)]
-struct Payer {
-    partyIdType: String,
-    partyId: u64,
+#[serde(untagged)]
+pub(crate) enum Reason {
+    Code(String),
+    Detailed { code: String },
+}
+
+impl Reason {
+    // NOTE: MTN sends a reason along with FAILED and REJECTED, they are
+    // kept on PaymentStatus::Rejected and PaymentStatus::Declined, any
+    // other status drops it
+    pub(crate) fn attach_to(
+        reason: Option<Reason>,
+        mut status: PaymentStatus,
+    ) -> PaymentStatus {
+        if let PaymentStatus::Rejected(ref mut failure_reason)
+        | PaymentStatus::Declined(ref mut failure_reason) = status
+        {
+            *failure_reason = reason.map(|reason| match reason {
+                Reason::Code(code) => FailureReason::from(&code[..]),
+                Reason::Detailed { code } => FailureReason::from(&code[..]),
+            });
+        }
+
+        status
+    }
 }
 
 #[allow(non_snake_case)]
 #[derive(Deserialize)]
 struct Payment {
-    amount: u64,
+    amount: String,
     currency: String,
-    financialTransactionId: u64,
-    externalId: u64,
+    financialTransactionId: Option<String>,
+    externalId: Option<String>,
     payer: Payer,
+    payerMessage: Option<String>,
+    payeeNote: Option<String>,
     status: String,
+    reason: Option<Reason>,
 }
 
+impl Payment {
+    fn into_transaction(self, reference_id: Uuid) -> Result<Transaction> {
+        let status: PaymentStatus = Reason::attach_to(
+            self.reason,
+            PaymentStatus::from_str(&self.status)?,
+        );
+
+        Ok(Transaction {
+            reference_id,
+            amount: self.amount,
+            currency: self.currency,
+            financial_transaction_id: self.financialTransactionId,
+            external_id: self.externalId,
+            payer: self.payer,
+            payer_message: self.payerMessage,
+            payee_note: self.payeeNote,
+            status,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BasicUserInfo {
+    pub given_name: String,
+    pub family_name: String,
+    pub birthdate: Option<String>,
+    pub locale: Option<String>,
+    pub gender: Option<String>,
+    pub status: Option<String>,
+}
+
+impl BasicUserInfo {
+    // NOTE: given name and family initial as shown before checkout, e.g.
+    // "KWAME M." for Kwame Mensah
+    pub fn display_name(&self) -> String {
+        let given_name: String = self.given_name.trim().to_uppercase();
+
+        match self.family_name.trim().chars().next() {
+            Some(initial) => format!(
+                "{} {}.",
+                given_name,
+                initial.to_uppercase().collect::<String>()
+            ),
+            None => given_name,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AccountHolderStatus {
+    result: bool,
+}
+
+#[allow(clippy::new_ret_no_self)]
 pub trait IClient {
     fn new(config: &Config) -> Result<Client>;
+    fn with_transport(
+        config: &Config,
+        transport: Arc<dyn Transport>,
+    ) -> Result<Client>;
     fn authorize_collections(&mut self) -> Result<&Client>;
+    fn disbursements(&mut self) -> Result<&mut DisbursementClient>;
+    fn remittances(&mut self) -> Result<&mut RemittanceClient>;
     fn request_to_pay(
         &mut self,
-        amount: u64,
-        currency: Currency,
-        msisdn: &Msisdn,
+        request: &PaymentRequest,
         callback_url: Option<&Url>,
     ) -> Result<Uuid>;
     fn request_to_pay_status(
         &mut self,
         reference_id: &Uuid,
-    ) -> Result<PaymentStatus>;
+    ) -> Result<Transaction>;
+    fn request_to_pay_delivery_notification(
+        &mut self,
+        reference_id: &Uuid,
+        message: &str,
+        language: Option<&str>,
+    ) -> Result<()>;
+    fn request_to_withdraw(
+        &mut self,
+        request: &PaymentRequest,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid>;
+    fn request_to_withdraw_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transaction>;
+    fn create_invoice(
+        &mut self,
+        invoice: &Invoice,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid>;
+    fn get_invoice_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<InvoiceDetails>;
+    fn cancel_invoice(
+        &mut self,
+        reference_id: &Uuid,
+        external_id: &str,
+    ) -> Result<()>;
+    fn create_pre_approval(
+        &mut self,
+        pre_approval: &PreApproval,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid>;
+    fn get_pre_approval_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<PreApprovalDetails>;
+    fn get_approved_pre_approvals(
+        &mut self,
+        payer: &Party,
+    ) -> Result<Vec<ApprovedPreApproval>>;
+    fn cancel_pre_approval(&mut self, pre_approval_id: &str) -> Result<()>;
+    fn create_payment(
+        &mut self,
+        bill_payment: &BillPayment,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid>;
+    fn get_payment_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<BillPaymentDetails>;
     fn get_balance(&mut self) -> Result<Balance>;
+    fn get_balance_in(&mut self, currency: Currency) -> Result<Balance>;
+    fn validate_account_holder(
+        &mut self,
+        account_holder: &Party,
+    ) -> Result<bool>;
+    fn get_basic_user_info(&mut self, msisdn: &Msisdn)
+        -> Result<BasicUserInfo>;
+    fn bc_authorize(
+        &mut self,
+        msisdn: &Msisdn,
+        scopes: &[Scope],
+        callback_url: Option<&Url>,
+    ) -> Result<ConsentRequest>;
+    fn consent_token(
+        &mut self,
+        consent_request: &ConsentRequest,
+    ) -> Result<ConsentToken>;
+    fn get_user_info_with_consent(
+        &mut self,
+        consent_token: &ConsentToken,
+    ) -> Result<UserInfoWithConsent>;
 }
 
 impl IClient for Client {
-    // TODO: preformat all endpoint urls in contructor
     fn new(config: &Config) -> Result<Client> {
-        let http_client: blocking::Client = blocking::Client::builder()
-            .http1_title_case_headers()
-            .build()?;
-
-        let base_url: String;
-        let target_environment: &str;
-
-        if let Some(url) = &config.base_url {
-            if url.ends_with("/") {
-                base_url = url.clone();
-            } else {
-                base_url = format!("{}/", url);
-            };
-
-            if url.starts_with(PRODUCTION_BASE_URL) {
-                target_environment = PRODUCTION;
-            } else {
-                target_environment = SANDBOX;
-            };
-        } else {
-            debug!(
-                "[mini-mtn-momo] using fallback sandbox environment \
-                located @ {}",
-                SANDBOX_BASE_URL
-            );
-
-            base_url = SANDBOX_BASE_URL.to_string();
-            target_environment = SANDBOX;
-        };
-
-        let callback_host: &str = if let Some(domain) = &config.callback_host {
-            domain
-        } else {
-            debug!(
-                "[mini-mtn-momo] using fallback callback host \"{}\"",
-                FALLBACK_CALLBACK_HOST
-            );
+        Client::with_transport(config, Arc::new(ReqwestTransport::new()?))
+    }
 
-            FALLBACK_CALLBACK_HOST
-        };
+    fn with_transport(
+        config: &Config,
+        transport: Arc<dyn Transport>,
+    ) -> Result<Client> {
+        let collections: Session =
+            Session::new(config, Product::Collection, transport.clone())?;
 
         let mut client: Client = Client {
-            http_client,
-            target_environment: target_environment.to_string(),
-            username: config.username.clone(),
-            password: config.password.clone(),
-            subscription_key: config.subscription_key.clone(),
-            collections_access_token: "".to_string(),
-            base_url,
-            callback_host: callback_host.to_string(),
-            reauthorize: true,
+            target_environment: collections.state.target_environment.clone(),
+            base_url: collections.state.base_url.clone(),
+            callback_host: collections.state.callback_host.clone(),
+            collections,
+            disbursements: None,
+            remittances: None,
         };
 
         client.authorize_collections()?;
 
+        if config.disbursement.is_some() {
+            client.disbursements = Some(DisbursementClient::with_transport(
+                config,
+                transport.clone(),
+            )?);
+        }
+
+        if config.remittance.is_some() {
+            client.remittances =
+                Some(RemittanceClient::with_transport(config, transport)?);
+        }
+
         Ok(client)
     }
 
     fn authorize_collections(&mut self) -> Result<&Client> {
-        self.reauthorize = false;
-
-        let url: String = format!("{}collection/token/", &self.base_url);
-
-        let response: blocking::Response = self
-            .http_client
-            .post(&url)
-            .basic_auth(&self.username, Some(&self.password))
-            .header("Ocp-Apim-Subscription-Key", &self.subscription_key)
-            .header("Content-Length", "0")
-            .send()?;
-
-        if response.status() != StatusCode::OK {
-            bail!(
-                "authorizing collections failed - http status {:?}\n{}",
-                response.status(),
-                response.text()?,
-            );
-        } else {
-            self.collections_access_token =
-                response.json::<Authorization>()?.access_token;
+        self.collections.authorize()?;
 
-            self.reauthorize = true;
+        Ok(self)
+    }
+
+    fn disbursements(&mut self) -> Result<&mut DisbursementClient> {
+        if let Some(disbursements) = self.disbursements.as_mut() {
+            Ok(disbursements)
+        } else {
+            Err(MomoError::Config(format!(
+                "no {} credentials configured",
+                Product::Disbursement
+            )))
+        }
+    }
 
-            Ok(self)
+    fn remittances(&mut self) -> Result<&mut RemittanceClient> {
+        if let Some(remittances) = self.remittances.as_mut() {
+            Ok(remittances)
+        } else {
+            Err(MomoError::Config(format!(
+                "no {} credentials configured",
+                Product::Remittance
+            )))
         }
     }
 
     fn request_to_pay(
         &mut self,
-        amount: u64,
-        currency: Currency,
-        msisdn: &Msisdn,
+        request: &PaymentRequest,
         callback_url: Option<&Url>,
     ) -> Result<Uuid> {
-        let url: String =
-            format!("{}collection/v1_0/requesttopay/", &self.base_url);
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
 
-        let reference_id: Uuid = Uuid::new_v4();
-        let reference_id_string: String = reference_id.to_string();
-
-        let cb_url: &Url = if let Some(url) = callback_url {
-            url
-        } else if self.callback_host.ends_with("mocky.io") {
-            debug!(
-                "[mini-mtn-momo] using fallback callback url \"{}\"",
-                FALLBACK_CALLBACK_URL.as_str()
-            );
+        self.collections.execute(collection::submit_payment_request(
+            "v1_0/requesttopay",
+            "requesting to pay",
+            request,
+            cb_url,
+        )?)
+    }
 
-            &FALLBACK_CALLBACK_URL
-        } else {
-            bail!(
-                "when having specified a custom callback host a callback url \
-                 with the same host is required for every request to pay"
-            );
-        };
+    fn request_to_pay_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transaction> {
+        let path: String = format!("v1_0/requesttopay/{}", reference_id);
 
-        let body: String = json!({
-            "amount": amount,
-            "currency": currency.to_string(),
-            "externalId": &reference_id_string,
-            "payer": {
-              "partyIdType": "MSISDN",
-              "partyId": msisdn.to_string(),
-            },
-            "payerMessage": "it's time to pay :)",
-            "payeeNote": "TODO",
-        })
-        .to_string();
-
-        let response: blocking::Response = self
-            .http_client
-            .post(&url)
-            .bearer_auth(&self.collections_access_token)
-            .header("X-Callback-Url", cb_url.as_str())
-            .header("X-Reference-Id", &reference_id_string)
-            .header("X-Target-Environment", &self.target_environment)
-            .header("Ocp-Apim-Subscription-Key", &self.subscription_key)
-            .header("Content-Type", "application/json")
-            .header("Content-Length", body.chars().count())
-            .body(body)
-            .send()?;
-
-        let status: StatusCode = response.status();
-
-        if status == StatusCode::ACCEPTED {
-            Ok(reference_id)
-        } else if status == StatusCode::UNAUTHORIZED && self.reauthorize {
-            debug!("currently unauthorized, attempting reauthorization...");
-
-            self.authorize_collections()?;
-
-            self.request_to_pay(amount, currency, msisdn, callback_url)
-        } else {
-            bail!(
-                "payment request failed - http status {:?} - \
-                reference id {}\n{}",
-                response.status(),
-                reference_id_string,
-                response.text()?
-            );
-        }
+        self.collections
+            .execute(collection::get_transaction(&path, reference_id))
     }
 
-    fn request_to_pay_status(
+    fn request_to_pay_delivery_notification(
         &mut self,
         reference_id: &Uuid,
-    ) -> Result<PaymentStatus> {
-        let url: String = format!(
-            "{}collection/v1_0/requesttopay/{}",
-            &self.base_url,
-            reference_id.to_string()
-        );
+        message: &str,
+        language: Option<&str>,
+    ) -> Result<()> {
+        self.collections.execute(
+            collection::request_to_pay_delivery_notification(
+                reference_id,
+                message,
+                language,
+            )?,
+        )
+    }
 
-        let response: blocking::Response = self
-            .http_client
-            .get(&url)
-            .bearer_auth(&self.collections_access_token)
-            .header("X-Target-Environment", &self.target_environment)
-            .header("Ocp-Apim-Subscription-Key", &self.subscription_key)
-            .send()?;
+    fn request_to_withdraw(
+        &mut self,
+        request: &PaymentRequest,
+        callback_url: Option<&Url>,
+        version: ApiVersion,
+    ) -> Result<Uuid> {
+        let path: String = format!("{}/requesttowithdraw", version);
+
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections.execute(collection::submit_payment_request(
+            &path,
+            "requesting to withdraw",
+            request,
+            cb_url,
+        )?)
+    }
 
-        let status: StatusCode = response.status();
+    fn request_to_withdraw_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<Transaction> {
+        let path: String = format!("v1_0/requesttowithdraw/{}", reference_id);
 
-        if status == StatusCode::OK {
-            let payment_status_string: String =
-                response.json::<Payment>()?.status;
+        self.collections
+            .execute(collection::get_transaction(&path, reference_id))
+    }
 
-            let payment_status: PaymentStatus =
-                PaymentStatus::from_str(&payment_status_string[..])?;
+    fn create_invoice(
+        &mut self,
+        invoice: &Invoice,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
 
-            Ok(payment_status)
-        } else if status == StatusCode::UNAUTHORIZED && self.reauthorize {
-            debug!("currently unauthorized, attempting reauthorization...");
+        self.collections
+            .execute(collection::create_invoice(invoice, cb_url)?)
+    }
 
-            self.authorize_collections()?;
+    fn get_invoice_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<InvoiceDetails> {
+        self.collections
+            .execute(collection::get_invoice_status(reference_id))
+    }
 
-            self.request_to_pay_status(&reference_id)
-        } else {
-            bail!(
-                "requesting payment status failed - http status {:?} - \
-                    reference id {}\n{}",
-                response.status(),
-                reference_id.to_string(),
-                response.text()?
-            );
-        }
+    fn cancel_invoice(
+        &mut self,
+        reference_id: &Uuid,
+        external_id: &str,
+    ) -> Result<()> {
+        self.collections
+            .execute(collection::cancel_invoice(reference_id, external_id)?)
+    }
+
+    fn create_pre_approval(
+        &mut self,
+        pre_approval: &PreApproval,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections
+            .execute(collection::create_pre_approval(pre_approval, cb_url)?)
+    }
+
+    fn get_pre_approval_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<PreApprovalDetails> {
+        self.collections
+            .execute(collection::get_pre_approval_status(reference_id))
+    }
+
+    fn get_approved_pre_approvals(
+        &mut self,
+        payer: &Party,
+    ) -> Result<Vec<ApprovedPreApproval>> {
+        self.collections
+            .execute(collection::get_approved_pre_approvals(payer))
+    }
+
+    fn cancel_pre_approval(&mut self, pre_approval_id: &str) -> Result<()> {
+        self.collections
+            .execute(collection::cancel_pre_approval(pre_approval_id))
+    }
+
+    fn create_payment(
+        &mut self,
+        bill_payment: &BillPayment,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        let cb_url: &Url = self.collections.callback_url(callback_url)?;
+
+        self.collections
+            .execute(collection::create_payment(bill_payment, cb_url)?)
+    }
+
+    fn get_payment_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<BillPaymentDetails> {
+        self.collections
+            .execute(collection::get_payment_status(reference_id))
     }
 
     fn get_balance(&mut self) -> Result<Balance> {
-        let url: String =
-            format!("{}collection/v1_0/account/balance", &self.base_url);
+        self.collections
+            .execute(collection::get_balance_at("v1_0/account/balance"))
+    }
 
-        let response: blocking::Response = self
-            .http_client
-            .get(&url)
-            .bearer_auth(&self.collections_access_token)
-            .header("X-Target-Environment", &self.target_environment)
-            .header("Ocp-Apim-Subscription-Key", &self.subscription_key)
-            .send()?;
+    // NOTE: the balance of one wallet of a multi-currency account
+    fn get_balance_in(&mut self, currency: Currency) -> Result<Balance> {
+        let path: String = format!("v1_0/account/balance/{}", currency);
 
-        let status: StatusCode = response.status();
+        self.collections.execute(collection::get_balance_at(&path))
+    }
 
-        if status == StatusCode::OK {
-            let balance: Balance = response.json::<Balance>()?;
+    fn validate_account_holder(
+        &mut self,
+        account_holder: &Party,
+    ) -> Result<bool> {
+        self.collections
+            .execute(collection::validate_account_holder(account_holder))
+    }
 
-            Ok(balance)
-        } else if status == StatusCode::UNAUTHORIZED && self.reauthorize {
-            debug!("currently unauthorized, attempting reauthorization...");
+    fn get_basic_user_info(
+        &mut self,
+        msisdn: &Msisdn,
+    ) -> Result<BasicUserInfo> {
+        self.collections
+            .execute(collection::get_basic_user_info(msisdn))
+    }
 
-            self.authorize_collections()?;
+    fn bc_authorize(
+        &mut self,
+        msisdn: &Msisdn,
+        scopes: &[Scope],
+        callback_url: Option<&Url>,
+    ) -> Result<ConsentRequest> {
+        self.collections.execute(collection::bc_authorize(
+            msisdn,
+            scopes,
+            callback_url,
+        )?)
+    }
 
-            self.get_balance()
-        } else {
-            bail!(
-                "getting wallet balance failed - http status {:?}\n{}",
-                response.status(),
-                response.text()?
-            );
+    // NOTE: blocks until the account holder answered the consent prompt,
+    // fails with MomoError::Consent if they denied it or never answered
+    fn consent_token(
+        &mut self,
+        consent_request: &ConsentRequest,
+    ) -> Result<ConsentToken> {
+        let mut consent_poller: ConsentPoller =
+            ConsentPoller::new(consent_request);
+
+        loop {
+            let consent_poll: ConsentPoll = self.collections.execute(
+                collection::poll_consent_token(&consent_request.auth_req_id),
+            )?;
+
+            match consent_poller.step(consent_poll)? {
+                ConsentStep::Granted(consent_token) => {
+                    return Ok(consent_token)
+                }
+                ConsentStep::Wait(interval) => {
+                    debug!(
+                        "[mini-mtn-momo] consent pending, polling again in {}s",
+                        interval.as_secs()
+                    );
+
+                    thread::sleep(interval);
+                }
+            }
         }
     }
+
+    fn get_user_info_with_consent(
+        &mut self,
+        consent_token: &ConsentToken,
+    ) -> Result<UserInfoWithConsent> {
+        self.collections
+            .execute(collection::get_user_info_with_consent(consent_token))
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::unused_unit)]
 mod mtn_momo_mini_unit_tests {
     use super::*;
 
+    #[test]
+    fn picking_per_product_credentials() {
+        let config: Config = Config {
+            username: String::from("collection-user"),
+            password: String::from("collection-password"),
+            subscription_key: String::from("collection-key"),
+            base_url: None,
+            callback_host: None,
+            disbursement: Some(Credentials {
+                username: String::from("disbursement-user"),
+                password: String::from("disbursement-password"),
+                subscription_key: String::from("disbursement-key"),
+            }),
+            remittance: None,
+            retry: None,
+        };
+
+        assert_eq!(
+            config
+                .credentials(Product::Disbursement)
+                .expect("disbursement credentials")
+                .subscription_key,
+            "disbursement-key"
+        );
+        assert_eq!(
+            config
+                .credentials(Product::Collection)
+                .expect("collection credentials")
+                .username,
+            "collection-user"
+        );
+
+        match config.credentials(Product::Remittance) {
+            Err(MomoError::Config(_)) => {}
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deserializing_a_successful_payment() {
+        let json: &str = r#"{
+            "amount": "419",
+            "currency": "EUR",
+            "financialTransactionId": "1837462849",
+            "externalId": "order-123",
+            "payer": { "partyIdType": "MSISDN", "partyId": "233542373722" },
+            "payerMessage": "Order 123",
+            "payeeNote": "order 123",
+            "status": "SUCCESSFUL"
+        }"#;
+
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let transaction: Transaction = serde_json::from_str::<Payment>(json)
+            .expect("payment")
+            .into_transaction(reference_id)
+            .expect("transaction");
+
+        assert_eq!(transaction.reference_id, reference_id);
+        assert_eq!(transaction.amount, "419");
+        assert_eq!(
+            transaction.financial_transaction_id.as_deref(),
+            Some("1837462849")
+        );
+        assert_eq!(transaction.external_id.as_deref(), Some("order-123"));
+        assert_eq!(transaction.payer.party_id_type, PartyIdType::Msisdn);
+        assert_eq!(transaction.payer.party_id, "233542373722");
+        assert_eq!(transaction.status, PaymentStatus::Resolved);
+    }
+
+    #[test]
+    fn deserializing_a_failed_payment_with_a_reason() {
+        let bare: &str = r#"{
+            "amount": "419",
+            "currency": "EUR",
+            "externalId": "order-123",
+            "payer": { "partyIdType": "MSISDN", "partyId": "233542373722" },
+            "status": "FAILED",
+            "reason": "NOT_ENOUGH_FUNDS"
+        }"#;
+        let detailed: &str = r#"{
+            "amount": "419",
+            "currency": "EUR",
+            "externalId": "order-123",
+            "payer": { "partyIdType": "MSISDN", "partyId": "233542373722" },
+            "status": "FAILED",
+            "reason": { "code": "NOT_ENOUGH_FUNDS", "message": "poor" }
+        }"#;
+
+        for json in &[bare, detailed] {
+            let transaction: Transaction =
+                serde_json::from_str::<Payment>(json)
+                    .expect("payment")
+                    .into_transaction(Uuid::new_v4())
+                    .expect("transaction");
+
+            assert_eq!(
+                transaction.status,
+                PaymentStatus::Rejected(Some(FailureReason::NotEnoughFunds))
+            );
+        }
+    }
+
+    #[test]
+    fn delivery_notification_validates() {
+        assert!(validate_delivery_notification(
+            "Thanks, order #123 confirmed",
+            Some("en")
+        )
+        .is_ok());
+        assert!(validate_delivery_notification("Thanks", None).is_ok());
+    }
+
+    #[test]
+    fn delivery_notification_fails_on_empty_or_too_long_message() {
+        assert!(validate_delivery_notification("", None).is_err());
+        assert!(validate_delivery_notification(
+            &"a".repeat(MAX_MESSAGE_LENGTH + 1usize),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn delivery_notification_fails_on_invalid_language() {
+        assert!(validate_delivery_notification("Thanks", Some("EN")).is_err());
+        assert!(validate_delivery_notification("Thanks", Some("eng")).is_err());
+    }
+
+    #[test]
+    fn deserializing_basic_user_info() {
+        let json: &str = r#"{
+            "given_name": "Kwame",
+            "family_name": "Mensah",
+            "birthdate": "1976-08-13",
+            "locale": "sv_SE",
+            "gender": "M",
+            "status": "ACTIVE"
+        }"#;
+
+        let basic_user_info: BasicUserInfo =
+            serde_json::from_str(json).expect("basic user info");
+
+        assert_eq!(basic_user_info.birthdate.as_deref(), Some("1976-08-13"));
+        assert_eq!(basic_user_info.display_name(), "KWAME M.");
+    }
+
+    #[test]
+    fn basic_user_info_display_name_without_family_name() {
+        let basic_user_info: BasicUserInfo = BasicUserInfo {
+            given_name: String::from("Kwame"),
+            family_name: String::new(),
+            birthdate: None,
+            locale: None,
+            gender: None,
+            status: None,
+        };
+
+        assert_eq!(basic_user_info.display_name(), "KWAME");
+    }
+
     #[test]
     fn roundtripping_payment_status_pending() -> () {
         let string: String = PaymentStatus::Pending.to_string();
@@ -338,14 +968,14 @@ mod mtn_momo_mini_unit_tests {
 
     #[test]
     fn roundtripping_payment_status_rejected() -> () {
-        let string: String = PaymentStatus::Rejected.to_string();
+        let string: String = PaymentStatus::Rejected(None).to_string();
 
         assert_eq!(string, "FAILED");
 
         let status: PaymentStatus =
             PaymentStatus::from_str(&string).expect("PaymentStatus::Rejected");
 
-        assert_eq!(status, PaymentStatus::Rejected);
+        assert_eq!(status, PaymentStatus::Rejected(None));
     }
 
     #[test]
@@ -361,7 +991,110 @@ mod mtn_momo_mini_unit_tests {
     }
 
     #[test]
-    fn payment_status_from_str_fails_on_unknown_status() {
-        assert!(PaymentStatus::from_str("UNKNOWN").is_err());
+    fn payment_status_from_str_falls_back_on_unknown_status() {
+        let status: PaymentStatus =
+            PaymentStatus::from_str("UNKNOWN").expect("PaymentStatus::Unknown");
+
+        assert_eq!(status, PaymentStatus::Unknown(String::from("UNKNOWN")));
+    }
+
+    fn payment_request() -> PaymentRequest {
+        PaymentRequest {
+            amount: 419u64,
+            currency: Currency::Cedi,
+            payer: Party::from(
+                Msisdn::parse("0542373722", &GHANA, None).expect("msisdn"),
+            ),
+            payer_message: String::from("Order #123 at Nugget Digital"),
+            payee_note: String::from("order 123"),
+            external_id: String::from("order-123"),
+            reference_id: None,
+        }
+    }
+
+    #[test]
+    fn reference_id_for_is_deterministic() {
+        let reference_id: Uuid = reference_id_for("order-123");
+
+        assert_eq!(reference_id, reference_id_for("order-123"));
+        assert_ne!(reference_id, reference_id_for("order-124"));
+        assert_eq!(reference_id.get_version_num(), 5usize);
+    }
+
+    // NOTE: reference ids derived by earlier releases have to stay the
+    // same, otherwise retried payments would no longer be deduplicated
+    #[test]
+    fn reference_id_for_is_stable_across_releases() {
+        assert_eq!(
+            reference_id_for("order-123"),
+            Uuid::parse_str("19d43db7-b922-569f-b9b3-fa8f9bc869e4")
+                .expect("uuid")
+        );
+    }
+
+    #[test]
+    fn payment_request_validates() {
+        assert!(payment_request().validate().is_ok());
+    }
+
+    #[test]
+    fn payment_request_allows_empty_message_and_note() {
+        let request: PaymentRequest = PaymentRequest {
+            payer_message: String::new(),
+            payee_note: String::new(),
+            ..payment_request()
+        };
+
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn payment_request_fails_on_zero_amount() {
+        let request: PaymentRequest = PaymentRequest {
+            amount: 0u64,
+            ..payment_request()
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn payment_request_fails_on_too_long_payer_message() {
+        let request: PaymentRequest = PaymentRequest {
+            payer_message: "a".repeat(MAX_MESSAGE_LENGTH + 1usize),
+            ..payment_request()
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn payment_request_fails_on_unsupported_payee_note_characters() {
+        let request: PaymentRequest = PaymentRequest {
+            payee_note: String::from("it's time to pay <b>now</b>"),
+            ..payment_request()
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn payment_request_fails_on_empty_external_id() {
+        let request: PaymentRequest = PaymentRequest {
+            external_id: String::new(),
+            ..payment_request()
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn payment_request_fails_on_too_long_external_id() {
+        let request: PaymentRequest = PaymentRequest {
+            external_id: "1".repeat(MAX_EXTERNAL_ID_LENGTH + 1usize),
+            ..payment_request()
+        };
+
+        assert!(request.validate().is_err());
     }
 }
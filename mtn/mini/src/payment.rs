@@ -0,0 +1,262 @@
+use std::fmt;
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::{validate_external_id, validate_message, Reason};
+
+lazy_static! {
+    static ref CUSTOMER_REFERENCE: Regex =
+        Regex::new(r"^[A-Za-z0-9/_\-]{1,64}$").expect("regex");
+    static ref SERVICE_PROVIDER_NAME: Regex =
+        Regex::new(r"^[A-Za-z0-9._\-]{1,64}$").expect("regex");
+}
+
+// NOTE: the customer's account with the service provider, e.g. the meter
+// or contract number a utility bill is paid for
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CustomerReference(String);
+
+impl CustomerReference {
+    pub fn parse(customer_reference: &str) -> Result<CustomerReference> {
+        if !CUSTOMER_REFERENCE.is_match(customer_reference) {
+            return Err(MomoError::Validation(format!(
+                "customer reference {:?} must be 1 to 64 ascii letters, \
+                 digits, slashes, dashes or underscores",
+                customer_reference
+            )));
+        }
+
+        Ok(CustomerReference(customer_reference.to_string()))
+    }
+}
+
+impl fmt::Display for CustomerReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0).fmt(f)
+    }
+}
+
+// NOTE: the MTN user name of the biller receiving the payment
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ServiceProviderName(String);
+
+impl ServiceProviderName {
+    pub fn parse(service_provider_name: &str) -> Result<ServiceProviderName> {
+        if !SERVICE_PROVIDER_NAME.is_match(service_provider_name) {
+            return Err(MomoError::Validation(format!(
+                "service provider name {:?} must be 1 to 64 ascii letters, \
+                 digits, dots, dashes or underscores",
+                service_provider_name
+            )));
+        }
+
+        Ok(ServiceProviderName(service_provider_name.to_string()))
+    }
+}
+
+impl fmt::Display for ServiceProviderName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0).fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BillPayment {
+    pub amount: u64,
+    pub currency: Currency,
+    pub customer_reference: CustomerReference,
+    pub service_provider_name: ServiceProviderName,
+    pub receiver_message: String,
+    pub sender_note: String,
+    // the caller's own identifier for this payment, e.g. an order id
+    pub external_transaction_id: String,
+    pub coupon_id: Option<String>,
+    pub product_id: Option<String>,
+    pub product_offering_id: Option<String>,
+    pub max_number_of_retries: Option<u32>,
+    pub include_sender_charges: bool,
+    // NOTE: sent as X-Reference-Id, a random one is generated if unset
+    pub reference_id: Option<Uuid>,
+}
+
+impl BillPayment {
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "bill payment amount must be greater than zero",
+            )));
+        }
+
+        validate_message("receiver message", &self.receiver_message)?;
+        validate_message("sender note", &self.sender_note)?;
+        validate_external_id(&self.external_transaction_id)
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        json!({
+            "externalTransactionId": &self.external_transaction_id,
+            "money": {
+              "amount": self.amount.to_string(),
+              "currency": self.currency.to_string(),
+            },
+            "customerReference": self.customer_reference.to_string(),
+            "serviceProviderUserName": self.service_provider_name.to_string(),
+            "couponId": &self.coupon_id,
+            "productId": &self.product_id,
+            "productOfferingId": &self.product_offering_id,
+            "receiverMessage": &self.receiver_message,
+            "senderNote": &self.sender_note,
+            "maxNumberOfRetries": self.max_number_of_retries,
+            "includeSenderCharges": self.include_sender_charges,
+        })
+        .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BillPaymentDetails {
+    pub reference_id: String,
+    pub status: PaymentStatus,
+    pub financial_transaction_id: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub(crate) struct BillPaymentResponse {
+    referenceId: String,
+    status: String,
+    financialTransactionId: Option<String>,
+    reason: Option<Reason>,
+}
+
+impl BillPaymentResponse {
+    pub(crate) fn into_bill_payment_details(
+        self,
+    ) -> Result<BillPaymentDetails> {
+        let status: PaymentStatus = Reason::attach_to(
+            self.reason,
+            PaymentStatus::from_str(&self.status)?,
+        );
+
+        Ok(BillPaymentDetails {
+            reference_id: self.referenceId,
+            status,
+            financial_transaction_id: self.financialTransactionId,
+        })
+    }
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_payment_unit_tests {
+    use url::Url;
+
+    use super::*;
+    use crate::call::Call;
+    use crate::collection;
+
+    fn bill_payment() -> BillPayment {
+        BillPayment {
+            amount: 419u64,
+            currency: Currency::Cedi,
+            customer_reference: CustomerReference::parse("METER-0042/7")
+                .expect("customer reference"),
+            service_provider_name: ServiceProviderName::parse("ecg.prepaid")
+                .expect("service provider name"),
+            receiver_message: String::from("Meter 0042 top up"),
+            sender_note: String::from("electricity March"),
+            external_transaction_id: String::from("bill-123"),
+            coupon_id: None,
+            product_id: None,
+            product_offering_id: None,
+            max_number_of_retries: Some(3u32),
+            include_sender_charges: false,
+            reference_id: None,
+        }
+    }
+
+    #[test]
+    fn bill_payment_validates() {
+        assert!(bill_payment().validate().is_ok());
+    }
+
+    #[test]
+    fn bill_payment_fails_on_zero_amount() {
+        let bill_payment: BillPayment = BillPayment {
+            amount: 0u64,
+            ..bill_payment()
+        };
+
+        assert!(bill_payment.validate().is_err());
+    }
+
+    #[test]
+    fn customer_reference_fails_on_invalid_characters() {
+        assert!(CustomerReference::parse("").is_err());
+        assert!(CustomerReference::parse("METER 0042").is_err());
+        assert!(CustomerReference::parse(&"1".repeat(65usize)).is_err());
+    }
+
+    #[test]
+    fn service_provider_name_fails_on_invalid_characters() {
+        assert!(ServiceProviderName::parse("").is_err());
+        assert!(ServiceProviderName::parse("ecg prepaid").is_err());
+    }
+
+    #[test]
+    fn serializing_a_bill_payment() {
+        let json: serde_json::Value =
+            serde_json::from_str(&bill_payment().to_json()).expect("json");
+
+        assert_eq!(json["money"]["amount"], "419");
+        assert_eq!(json["money"]["currency"], "GHS");
+        assert_eq!(json["customerReference"], "METER-0042/7");
+        assert_eq!(json["serviceProviderUserName"], "ecg.prepaid");
+        assert_eq!(json["maxNumberOfRetries"], 3u32);
+    }
+
+    #[test]
+    fn deserializing_a_failed_bill_payment() {
+        let json: &str = r#"{
+            "referenceId": "f5b8e1a4-2d4c-4d55-9b4a-2c1b3f2a0e11",
+            "status": "FAILED",
+            "reason": { "code": "PAYEE_NOT_FOUND", "message": "unknown" }
+        }"#;
+
+        let bill_payment_details: BillPaymentDetails =
+            serde_json::from_str::<BillPaymentResponse>(json)
+                .expect("bill payment response")
+                .into_bill_payment_details()
+                .expect("bill payment details");
+
+        assert_eq!(
+            bill_payment_details.status,
+            PaymentStatus::Rejected(Some(FailureReason::PayeeNotFound))
+        );
+    }
+
+    #[test]
+    fn sending_the_caller_supplied_reference_id() {
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let call: Call<Uuid> = collection::create_payment(
+            &BillPayment {
+                reference_id: Some(reference_id),
+                ..bill_payment()
+            },
+            &Url::parse("https://nugget.digital/momo").expect("url"),
+        )
+        .expect("create_payment");
+
+        assert!(call.request.headers.contains(&(
+            String::from("X-Reference-Id"),
+            reference_id.to_string()
+        )));
+    }
+}
@@ -0,0 +1,214 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::{party_json, validate_message, Payer, Reason};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PreApproval {
+    pub payer: Party,
+    pub payer_currency: Currency,
+    // shown to the payer on the approval prompt
+    pub payer_message: String,
+    // how long debits may be taken without asking the payer again
+    pub validity_time: Duration,
+    // NOTE: sent as X-Reference-Id, a random one is generated if unset
+    pub reference_id: Option<Uuid>,
+}
+
+impl PreApproval {
+    pub fn validate(&self) -> Result<()> {
+        // NOTE: MTN takes the validity time in whole seconds
+        if self.validity_time.as_secs() == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "pre-approval validity time must be at least one second",
+            )));
+        }
+
+        validate_message("payer message", &self.payer_message)
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        json!({
+            "payer": party_json(&self.payer),
+            "payerCurrency": self.payer_currency.to_string(),
+            "payerMessage": &self.payer_message,
+            "validityTime": self.validity_time.as_secs(),
+        })
+        .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PreApprovalDetails {
+    pub payer: Payer,
+    pub payer_currency: String,
+    pub payer_message: Option<String>,
+    pub status: PaymentStatus,
+    pub expiration_date_time: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub(crate) struct PreApprovalResponse {
+    payer: Payer,
+    payerCurrency: String,
+    payerMessage: Option<String>,
+    status: String,
+    expirationDateTime: Option<String>,
+    reason: Option<Reason>,
+}
+
+impl PreApprovalResponse {
+    pub(crate) fn into_pre_approval_details(
+        self,
+    ) -> Result<PreApprovalDetails> {
+        let status: PaymentStatus = Reason::attach_to(
+            self.reason,
+            PaymentStatus::from_str(&self.status)?,
+        );
+
+        Ok(PreApprovalDetails {
+            payer: self.payer,
+            payer_currency: self.payerCurrency,
+            payer_message: self.payerMessage,
+            status,
+            expiration_date_time: self.expirationDateTime,
+        })
+    }
+}
+
+// NOTE: a pre-approval the payer has agreed to as listed by MTN, the fri
+// fields are MTN's financial resource identifiers of payer and payee
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovedPreApproval {
+    pub pre_approval_id: String,
+    pub to_fri: Option<String>,
+    pub from_fri: Option<String>,
+    pub from_currency: Option<String>,
+    pub created_time: Option<String>,
+    pub approved_time: Option<String>,
+    pub expiry_time: Option<String>,
+    pub status: Option<String>,
+    pub message: Option<String>,
+    pub frequency: Option<String>,
+    pub start_date: Option<String>,
+    pub last_used_date: Option<String>,
+    pub offer: Option<String>,
+    pub external_id: Option<String>,
+    pub max_debit_amount: Option<String>,
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_preapproval_unit_tests {
+    use url::Url;
+
+    use super::*;
+    use crate::call::Call;
+    use crate::collection;
+    use crate::GHANA;
+
+    fn pre_approval() -> PreApproval {
+        PreApproval {
+            payer: Party::from(
+                Msisdn::parse("0542373722", &GHANA, None).expect("msisdn"),
+            ),
+            payer_currency: Currency::Cedi,
+            payer_message: String::from("Monthly Nugget Digital plan"),
+            validity_time: Duration::from_secs(31_536_000u64),
+            reference_id: None,
+        }
+    }
+
+    #[test]
+    fn pre_approval_validates() {
+        assert!(pre_approval().validate().is_ok());
+    }
+
+    #[test]
+    fn pre_approval_fails_on_zero_validity_time() {
+        let pre_approval: PreApproval = PreApproval {
+            validity_time: Duration::from_secs(0u64),
+            ..pre_approval()
+        };
+
+        assert!(pre_approval.validate().is_err());
+    }
+
+    #[test]
+    fn serializing_a_pre_approval() {
+        let json: serde_json::Value =
+            serde_json::from_str(&pre_approval().to_json()).expect("json");
+
+        assert_eq!(json["payer"]["partyId"], "233542373722");
+        assert_eq!(json["payerCurrency"], "GHS");
+        assert_eq!(json["validityTime"], 31_536_000u64);
+    }
+
+    #[test]
+    fn deserializing_a_pending_pre_approval() {
+        let json: &str = r#"{
+            "payer": { "partyIdType": "MSISDN", "partyId": "233542373722" },
+            "payerCurrency": "EUR",
+            "payerMessage": "Monthly Nugget Digital plan",
+            "status": "PENDING",
+            "expirationDateTime": "2021-06-01T12:00:00.000"
+        }"#;
+
+        let pre_approval_details: PreApprovalDetails =
+            serde_json::from_str::<PreApprovalResponse>(json)
+                .expect("pre-approval response")
+                .into_pre_approval_details()
+                .expect("pre-approval details");
+
+        assert_eq!(pre_approval_details.payer.party_id, "233542373722");
+        assert_eq!(pre_approval_details.status, PaymentStatus::Pending);
+    }
+
+    #[test]
+    fn deserializing_approved_pre_approvals() {
+        let json: &str = r#"[{
+            "preApprovalId": "5ff8a0b4-2d4c-4d55-9b4a-2c1b3f2a0e11",
+            "toFri": "FRI:nugget/USER",
+            "fromFri": "FRI:233542373722/MSISDN",
+            "fromCurrency": "EUR",
+            "status": "APPROVED",
+            "frequency": "MONTHLY",
+            "maxDebitAmount": "100"
+        }]"#;
+
+        let approved_pre_approvals: Vec<ApprovedPreApproval> =
+            serde_json::from_str(json).expect("approved pre-approvals");
+
+        assert_eq!(approved_pre_approvals.len(), 1usize);
+        assert_eq!(
+            approved_pre_approvals[0].frequency.as_deref(),
+            Some("MONTHLY")
+        );
+    }
+
+    #[test]
+    fn sending_the_caller_supplied_reference_id() {
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let call: Call<Uuid> = collection::create_pre_approval(
+            &PreApproval {
+                reference_id: Some(reference_id),
+                ..pre_approval()
+            },
+            &Url::parse("https://nugget.digital/momo").expect("url"),
+        )
+        .expect("create_pre_approval");
+
+        assert!(call.request.headers.contains(&(
+            String::from("X-Reference-Id"),
+            reference_id.to_string()
+        )));
+    }
+}
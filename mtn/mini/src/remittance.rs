@@ -0,0 +1,478 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use url::Url;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::call::{ApiRequest, Call, Context};
+use crate::disbursement::{get_balance_at, get_transfer, submit_transfer};
+use crate::session::Session;
+use crate::transport::{ReqwestTransport, Transport};
+use crate::{
+    party_json, validate_external_id, validate_message, Config, Payee, Product,
+    Reason, Transfer, TransferRequest, LANGUAGE_CODE,
+};
+
+lazy_static! {
+    static ref COUNTRY_CODE: Regex = Regex::new("^[A-Z]{2}$").expect("regex");
+    static ref CURRENCY_CODE: Regex = Regex::new("^[A-Z]{3}$").expect("regex");
+}
+
+#[derive(Debug)]
+pub struct RemittanceClient {
+    pub target_environment: String,
+    pub base_url: String,
+    pub callback_host: String,
+    remittances: Session,
+}
+
+// NOTE: the kind of identity document the originator presented to the
+// sending partner
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum IdentificationType {
+    Passport,
+    NationalId,
+    IdentityCard,
+    DriversLicense,
+    SocialSecurity,
+    AlienRegistration,
+    Other,
+}
+
+impl fmt::Display for IdentificationType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s: &str = match self {
+            IdentificationType::Passport => "PASS",
+            IdentificationType::NationalId => "NRIN",
+            IdentificationType::IdentityCard => "IDCD",
+            IdentificationType::DriversLicense => "DRLC",
+            IdentificationType::SocialSecurity => "SOCS",
+            IdentificationType::AlienRegistration => "AREG",
+            IdentificationType::Other => "OTHR",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// NOTE: know your customer details of the person abroad sending the money,
+// MTN calls them the payer
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Originator {
+    pub first_name: String,
+    pub surname: String,
+    pub identification_type: IdentificationType,
+    pub identification_number: String,
+    // ISO 639-1, e.g. "en"
+    pub language_code: String,
+    pub email: Option<Email>,
+    pub msisdn: Option<Msisdn>,
+}
+
+impl Originator {
+    pub fn validate(&self) -> Result<()> {
+        if self.first_name.trim().is_empty() || self.surname.trim().is_empty() {
+            return Err(MomoError::Validation(String::from(
+                "originator first name and surname are required",
+            )));
+        }
+
+        if self.identification_number.trim().is_empty() {
+            return Err(MomoError::Validation(String::from(
+                "originator identification number is required",
+            )));
+        }
+
+        if !LANGUAGE_CODE.is_match(&self.language_code) {
+            return Err(MomoError::Validation(format!(
+                "originator language {:?} is not an ISO 639-1 code",
+                self.language_code
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CashTransfer {
+    // the amount paid out to the payee in the payee's currency
+    pub amount: u64,
+    pub currency: Currency,
+    pub payee: Party,
+    // ISO 3166-1 alpha-2 country the money was sent from, e.g. "GB"
+    pub originating_country: String,
+    // the amount and ISO 4217 currency the originator paid in, e.g. "GBP"
+    pub original_amount: u64,
+    pub original_currency: String,
+    pub originator: Originator,
+    // written to the originator's transaction history
+    pub payer_message: String,
+    // shown to the payee in their transaction history
+    pub payee_note: String,
+    // the caller's own identifier for this transfer, e.g. a corridor order
+    pub external_id: String,
+    // NOTE: sent as X-Reference-Id, a random one is generated if unset
+    pub reference_id: Option<Uuid>,
+}
+
+impl CashTransfer {
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == 0u64 || self.original_amount == 0u64 {
+            return Err(MomoError::Validation(String::from(
+                "cash transfer amounts must be greater than zero",
+            )));
+        }
+
+        if !COUNTRY_CODE.is_match(&self.originating_country) {
+            return Err(MomoError::Validation(format!(
+                "originating country {:?} is not an ISO 3166-1 alpha-2 code",
+                self.originating_country
+            )));
+        }
+
+        if !CURRENCY_CODE.is_match(&self.original_currency) {
+            return Err(MomoError::Validation(format!(
+                "original currency {:?} is not an ISO 4217 code",
+                self.original_currency
+            )));
+        }
+
+        self.originator.validate()?;
+        validate_message("payer message", &self.payer_message)?;
+        validate_message("payee note", &self.payee_note)?;
+        validate_external_id(&self.external_id)
+    }
+
+    // NOTE: "orginatingCountry" is misspelled in MTN's api
+    pub(crate) fn to_json(&self) -> String {
+        json!({
+            "amount": self.amount.to_string(),
+            "currency": self.currency.to_string(),
+            "payee": party_json(&self.payee),
+            "externalId": &self.external_id,
+            "orginatingCountry": &self.originating_country,
+            "originalAmount": self.original_amount.to_string(),
+            "originalCurrency": &self.original_currency,
+            "payerMessage": &self.payer_message,
+            "payeeNote": &self.payee_note,
+            "payerIdentificationType":
+                self.originator.identification_type.to_string(),
+            "payerIdentificationNumber":
+                &self.originator.identification_number,
+            "payerFirstName": &self.originator.first_name,
+            "payerSurName": &self.originator.surname,
+            "payerLanguageCode": &self.originator.language_code,
+            "payerEmail": self.originator.email.as_ref().map(Email::to_string),
+            "payerMsisdn":
+                self.originator.msisdn.as_ref().map(Msisdn::to_string),
+        })
+        .to_string()
+    }
+}
+
+// NOTE: a Transfer along with what the originator paid abroad
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CashTransferDetails {
+    // the reference id the cash transfer was looked up with
+    pub reference_id: Uuid,
+    pub amount: String,
+    pub currency: String,
+    pub financial_transaction_id: Option<String>,
+    pub external_id: Option<String>,
+    pub payee: Payee,
+    pub originating_country: Option<String>,
+    pub original_amount: Option<String>,
+    pub original_currency: Option<String>,
+    pub payer_message: Option<String>,
+    pub payee_note: Option<String>,
+    pub status: PaymentStatus,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub(crate) struct CashTransferResponse {
+    amount: String,
+    currency: String,
+    financialTransactionId: Option<String>,
+    externalId: Option<String>,
+    payee: Payee,
+    orginatingCountry: Option<String>,
+    originalAmount: Option<String>,
+    originalCurrency: Option<String>,
+    payerMessage: Option<String>,
+    payeeNote: Option<String>,
+    status: String,
+    reason: Option<Reason>,
+}
+
+impl CashTransferResponse {
+    pub(crate) fn into_cash_transfer_details(
+        self,
+        reference_id: Uuid,
+    ) -> Result<CashTransferDetails> {
+        let status: PaymentStatus = Reason::attach_to(
+            self.reason,
+            PaymentStatus::from_str(&self.status)?,
+        );
+
+        Ok(CashTransferDetails {
+            reference_id,
+            amount: self.amount,
+            currency: self.currency,
+            financial_transaction_id: self.financialTransactionId,
+            external_id: self.externalId,
+            payee: self.payee,
+            originating_country: self.orginatingCountry,
+            original_amount: self.originalAmount,
+            original_currency: self.originalCurrency,
+            payer_message: self.payerMessage,
+            payee_note: self.payeeNote,
+            status,
+        })
+    }
+}
+
+#[allow(clippy::new_ret_no_self)]
+pub trait IRemittanceClient {
+    fn new(config: &Config) -> Result<RemittanceClient>;
+    fn with_transport(
+        config: &Config,
+        transport: Arc<dyn Transport>,
+    ) -> Result<RemittanceClient>;
+    fn authorize_remittances(&mut self) -> Result<&RemittanceClient>;
+    fn transfer(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid>;
+    fn transfer_status(&mut self, reference_id: &Uuid) -> Result<Transfer>;
+    fn cash_transfer(
+        &mut self,
+        cash_transfer: &CashTransfer,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid>;
+    fn cash_transfer_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<CashTransferDetails>;
+    fn get_balance(&mut self) -> Result<Balance>;
+}
+
+impl IRemittanceClient for RemittanceClient {
+    fn new(config: &Config) -> Result<RemittanceClient> {
+        RemittanceClient::with_transport(
+            config,
+            Arc::new(ReqwestTransport::new()?),
+        )
+    }
+
+    fn with_transport(
+        config: &Config,
+        transport: Arc<dyn Transport>,
+    ) -> Result<RemittanceClient> {
+        let remittances: Session =
+            Session::new(config, Product::Remittance, transport)?;
+
+        let mut client: RemittanceClient = RemittanceClient {
+            target_environment: remittances.state.target_environment.clone(),
+            base_url: remittances.state.base_url.clone(),
+            callback_host: remittances.state.callback_host.clone(),
+            remittances,
+        };
+
+        client.authorize_remittances()?;
+
+        Ok(client)
+    }
+
+    fn authorize_remittances(&mut self) -> Result<&RemittanceClient> {
+        self.remittances.authorize()?;
+
+        Ok(self)
+    }
+
+    fn transfer(
+        &mut self,
+        request: &TransferRequest,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        request.validate()?;
+
+        let cb_url: &Url = self.remittances.callback_url(callback_url)?;
+
+        self.remittances.execute(submit_transfer(
+            "v1_0/transfer",
+            "transferring",
+            request.reference_id,
+            request.to_json(),
+            cb_url,
+        ))
+    }
+
+    fn transfer_status(&mut self, reference_id: &Uuid) -> Result<Transfer> {
+        let path: String = format!("v1_0/transfer/{}", reference_id);
+
+        self.remittances.execute(get_transfer(
+            &path,
+            "requesting transfer status",
+            reference_id,
+        ))
+    }
+
+    fn cash_transfer(
+        &mut self,
+        cash_transfer: &CashTransfer,
+        callback_url: Option<&Url>,
+    ) -> Result<Uuid> {
+        cash_transfer.validate()?;
+
+        let cb_url: &Url = self.remittances.callback_url(callback_url)?;
+
+        self.remittances.execute(submit_transfer(
+            "v2_0/cashtransfer",
+            "sending cash transfer",
+            cash_transfer.reference_id,
+            cash_transfer.to_json(),
+            cb_url,
+        ))
+    }
+
+    fn cash_transfer_status(
+        &mut self,
+        reference_id: &Uuid,
+    ) -> Result<CashTransferDetails> {
+        let path: String = format!("v2_0/cashtransfer/{}", reference_id);
+
+        self.remittances
+            .execute(get_cash_transfer(&path, reference_id))
+    }
+
+    fn get_balance(&mut self) -> Result<Balance> {
+        self.remittances.execute(get_balance_at(
+            "v1_0/account/balance",
+            Product::Remittance,
+        ))
+    }
+}
+
+pub(crate) fn get_cash_transfer(
+    path: &str,
+    reference_id: &Uuid,
+) -> Call<CashTransferDetails> {
+    let reference_id: Uuid = *reference_id;
+
+    Call::ok_json(
+        ApiRequest::new(Method::GET, path).idempotent(),
+        "requesting cash transfer status",
+        Some(Context::ReferenceId(reference_id)),
+        move |response: CashTransferResponse| {
+            response.into_cash_transfer_details(reference_id)
+        },
+    )
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_remittance_unit_tests {
+    use super::*;
+    use crate::NIGERIA;
+
+    fn cash_transfer() -> CashTransfer {
+        CashTransfer {
+            amount: 50_000u64,
+            currency: Currency::Naira,
+            payee: Party::from(
+                Msisdn::parse("08012345678", &NIGERIA, None).expect("msisdn"),
+            ),
+            originating_country: String::from("GB"),
+            original_amount: 100u64,
+            original_currency: String::from("GBP"),
+            originator: Originator {
+                first_name: String::from("Ada"),
+                surname: String::from("Obi"),
+                identification_type: IdentificationType::Passport,
+                identification_number: String::from("123456789"),
+                language_code: String::from("en"),
+                email: Some(Email::parse("ada@example.com").expect("email")),
+                msisdn: None,
+            },
+            payer_message: String::from("family support"),
+            payee_note: String::from("From Ada in London"),
+            external_id: String::from("corridor-123"),
+            reference_id: None,
+        }
+    }
+
+    #[test]
+    fn cash_transfer_validates() {
+        assert!(cash_transfer().validate().is_ok());
+    }
+
+    #[test]
+    fn cash_transfer_fails_on_invalid_original_currency() {
+        let cash_transfer: CashTransfer = CashTransfer {
+            original_currency: String::from("pounds"),
+            ..cash_transfer()
+        };
+
+        assert!(cash_transfer.validate().is_err());
+    }
+
+    #[test]
+    fn cash_transfer_fails_on_missing_originator_identification() {
+        let mut cash_transfer: CashTransfer = cash_transfer();
+        cash_transfer.originator.identification_number = String::from(" ");
+
+        assert!(cash_transfer.validate().is_err());
+    }
+
+    #[test]
+    fn serializing_a_cash_transfer() {
+        let json: serde_json::Value =
+            serde_json::from_str(&cash_transfer().to_json()).expect("json");
+
+        assert_eq!(json["payee"]["partyIdType"], "MSISDN");
+        assert_eq!(json["orginatingCountry"], "GB");
+        assert_eq!(json["originalAmount"], "100");
+        assert_eq!(json["payerIdentificationType"], "PASS");
+        assert_eq!(json["payerEmail"], "ada@example.com");
+        assert_eq!(json["payerMsisdn"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn deserializing_a_pending_cash_transfer() {
+        let json: &str = r#"{
+            "amount": "50000",
+            "currency": "EUR",
+            "externalId": "corridor-123",
+            "payee": { "partyIdType": "MSISDN", "partyId": "2348012345678" },
+            "orginatingCountry": "GB",
+            "originalAmount": "100",
+            "originalCurrency": "GBP",
+            "status": "PENDING"
+        }"#;
+
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let cash_transfer_details: CashTransferDetails =
+            serde_json::from_str::<CashTransferResponse>(json)
+                .expect("cash transfer response")
+                .into_cash_transfer_details(reference_id)
+                .expect("cash transfer details");
+
+        assert_eq!(cash_transfer_details.reference_id, reference_id);
+        assert_eq!(
+            cash_transfer_details.original_currency.as_deref(),
+            Some("GBP")
+        );
+        assert_eq!(cash_transfer_details.status, PaymentStatus::Pending);
+    }
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use common::*;
+
+use crate::transport::HttpResponse;
+
+// NOTE: how idempotent calls (status lookups, balances and token fetches)
+// are resent when MTN or the network fails transiently, requests that
+// create something are never resent as MTN might have processed them
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    // including the first attempt, 1 disables retries
+    pub max_attempts: u32,
+    // doubled after every attempt up to max_backoff
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    // NOTE: waits a random duration between half and all of the backoff so
+    // that clients failing at the same time do not retry in lockstep
+    pub jitter: bool,
+    // http status codes, e.g. 503
+    pub transient_statuses: Vec<u16>,
+    pub transient_network_failures: Vec<NetworkFailure>,
+}
+
+impl RetryPolicy {
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1u32,
+            ..RetryPolicy::default()
+        }
+    }
+
+    // NOTE: attempt is the one that just failed, starting at 1
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent: u32 = attempt.saturating_sub(1u32).min(31u32);
+
+        let backoff: Duration = self
+            .initial_backoff
+            .checked_mul(1u32 << exponent)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        if self.jitter {
+            let half: Duration = backoff / 2u32;
+
+            half + half.mul_f64(rand::thread_rng().gen::<f64>())
+        } else {
+            backoff
+        }
+    }
+
+    pub(crate) fn is_transient(&self, result: &Result<HttpResponse>) -> bool {
+        match result {
+            Ok(response) => {
+                self.transient_statuses.contains(&response.status.as_u16())
+            }
+            Err(MomoError::Network { failure, .. }) => {
+                self.transient_network_failures.contains(failure)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3u32,
+            initial_backoff: Duration::from_millis(500u64),
+            max_backoff: Duration::from_secs(8u64),
+            jitter: true,
+            transient_statuses: vec![429u16, 500u16, 502u16, 503u16, 504u16],
+            transient_network_failures: vec![
+                NetworkFailure::Connect,
+                NetworkFailure::Timeout,
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_retry_unit_tests {
+    use http::StatusCode;
+
+    use super::*;
+
+    #[test]
+    fn backing_off_exponentially() {
+        let retry_policy: RetryPolicy = RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(retry_policy.backoff(1u32), Duration::from_millis(500u64));
+        assert_eq!(retry_policy.backoff(2u32), Duration::from_secs(1u64));
+        assert_eq!(retry_policy.backoff(3u32), Duration::from_secs(2u64));
+        assert_eq!(retry_policy.backoff(5u32), Duration::from_secs(8u64));
+        assert_eq!(retry_policy.backoff(64u32), Duration::from_secs(8u64));
+    }
+
+    #[test]
+    fn jittering_within_half_the_backoff() {
+        let retry_policy: RetryPolicy = RetryPolicy::default();
+
+        for _ in 0usize..100usize {
+            let backoff: Duration = retry_policy.backoff(2u32);
+
+            assert!(backoff >= Duration::from_millis(500u64));
+            assert!(backoff <= Duration::from_secs(1u64));
+        }
+    }
+
+    #[test]
+    fn transient_failures() {
+        let retry_policy: RetryPolicy = RetryPolicy::default();
+
+        let response = |status: StatusCode| -> Result<HttpResponse> {
+            Ok(HttpResponse {
+                status,
+                body: String::new(),
+            })
+        };
+
+        assert!(retry_policy
+            .is_transient(&response(StatusCode::SERVICE_UNAVAILABLE)));
+        assert!(!retry_policy.is_transient(&response(StatusCode::OK)));
+        assert!(!retry_policy.is_transient(&response(StatusCode::CONFLICT)));
+        assert!(retry_policy.is_transient(&Err(MomoError::Network {
+            failure: NetworkFailure::Timeout,
+            message: String::from("timed out"),
+        })));
+        assert!(!retry_policy.is_transient(&Err(MomoError::Network {
+            failure: NetworkFailure::Other,
+            message: String::from("invalid header"),
+        })));
+    }
+}
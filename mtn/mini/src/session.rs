@@ -0,0 +1,405 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use http::StatusCode;
+use log::debug;
+use reqwest::Method;
+use serde::Deserialize;
+use url::Url;
+
+use common::*;
+
+use crate::call::{ApiRequest, Auth, Call};
+use crate::transport::{AsyncTransport, HttpRequest, HttpResponse, Transport};
+use crate::{Config, Credentials, Product, RetryPolicy, TOKEN_REFRESH_MARGIN};
+
+#[derive(Deserialize)]
+pub(crate) struct Authorization {
+    access_token: String,
+    token_type: String,
+    expires_in: u64,
+}
+
+// NOTE: everything needed to talk to one MTN product (collection,
+// disbursement or remittance) which all share the same environment setup
+// but are authorized independently, the blocking and the async session
+// both keep one
+#[derive(Debug)]
+pub(crate) struct SessionState {
+    product: Product,
+    pub(crate) target_environment: String,
+    pub(crate) base_url: String,
+    pub(crate) callback_host: String,
+    username: String,
+    password: String,
+    subscription_key: String,
+    access_token: String,
+    token_expiry: Instant,
+    retry_policy: RetryPolicy,
+}
+
+impl SessionState {
+    // TODO: preformat all endpoint urls in contructor
+    pub(crate) fn new(
+        config: &Config,
+        product: Product,
+    ) -> Result<SessionState> {
+        let base_url: String;
+        let target_environment: &str;
+
+        if let Some(url) = &config.base_url {
+            if url.ends_with('/') {
+                base_url = url.clone();
+            } else {
+                base_url = format!("{}/", url);
+            };
+
+            if url.starts_with(PRODUCTION_BASE_URL) {
+                target_environment = PRODUCTION;
+            } else {
+                target_environment = SANDBOX;
+            };
+        } else {
+            debug!(
+                "[mini-mtn-momo] using fallback sandbox environment \
+                located @ {}",
+                SANDBOX_BASE_URL
+            );
+
+            base_url = SANDBOX_BASE_URL.to_string();
+            target_environment = SANDBOX;
+        };
+
+        let callback_host: &str = if let Some(domain) = &config.callback_host {
+            domain
+        } else {
+            debug!(
+                "[mini-mtn-momo] using fallback callback host \"{}\"",
+                FALLBACK_CALLBACK_HOST
+            );
+
+            FALLBACK_CALLBACK_HOST
+        };
+
+        let retry_policy: RetryPolicy = if let Some(policy) = &config.retry {
+            policy.clone()
+        } else {
+            debug!("[mini-mtn-momo] using default retry policy");
+
+            RetryPolicy::default()
+        };
+
+        let credentials: Credentials = config.credentials(product)?;
+
+        Ok(SessionState {
+            product,
+            target_environment: target_environment.to_string(),
+            base_url,
+            callback_host: callback_host.to_string(),
+            username: credentials.username,
+            password: credentials.password,
+            subscription_key: credentials.subscription_key,
+            access_token: "".to_string(),
+            token_expiry: Instant::now(),
+            retry_policy,
+        })
+    }
+
+    pub(crate) fn callback_url<'a>(
+        &self,
+        callback_url: Option<&'a Url>,
+    ) -> Result<&'a Url> {
+        if let Some(url) = callback_url {
+            Ok(url)
+        } else if self.callback_host.ends_with("mocky.io") {
+            debug!(
+                "[mini-mtn-momo] using fallback callback url \"{}\"",
+                FALLBACK_CALLBACK_URL.as_str()
+            );
+
+            Ok(&FALLBACK_CALLBACK_URL)
+        } else {
+            Err(MomoError::Config(String::from(
+                "when having specified a custom callback host a callback url \
+                 with the same host is required for every request",
+            )))
+        }
+    }
+
+    fn token_call(&self) -> Call<Authorization> {
+        let request: ApiRequest = ApiRequest::new(Method::POST, "token/")
+            .header("Content-Length", "0")
+            .auth(Auth::Basic)
+            .idempotent();
+
+        let what: String = format!("authorizing {}", self.product);
+
+        Call::ok_json(request, &what, None, Ok)
+    }
+
+    fn store(&mut self, authorization: Authorization) {
+        debug!(
+            "[mini-mtn-momo] authorized {}, {} expires in {}s",
+            self.product, authorization.token_type, authorization.expires_in
+        );
+
+        self.access_token = authorization.access_token;
+        self.token_expiry =
+            Instant::now() + Duration::from_secs(authorization.expires_in);
+    }
+
+    // NOTE: the access token is refreshed shortly before it expires
+    fn token_expiring(&self, request: &ApiRequest) -> bool {
+        match request.auth {
+            Auth::Product => {
+                Instant::now() + TOKEN_REFRESH_MARGIN >= self.token_expiry
+            }
+            _ => false,
+        }
+    }
+
+    // NOTE: a request rejected with 401 nonetheless is resent exactly once
+    // after reauthorizing
+    fn unauthorized(&self, request: &ApiRequest, status: StatusCode) -> bool {
+        match request.auth {
+            Auth::Product => status == StatusCode::UNAUTHORIZED,
+            _ => false,
+        }
+    }
+
+    // NOTE: how long to wait before resending a request whose attempt just
+    // failed, None if it must not be resent
+    fn retry_backoff(
+        &self,
+        request: &ApiRequest,
+        attempt: u32,
+        result: &Result<HttpResponse>,
+    ) -> Option<Duration> {
+        if request.idempotent
+            && attempt < self.retry_policy.max_attempts
+            && self.retry_policy.is_transient(result)
+        {
+            let backoff: Duration = self.retry_policy.backoff(attempt);
+
+            debug!(
+                "[mini-mtn-momo] attempt {} of {} failed transiently, \
+                 retrying in {}ms",
+                attempt,
+                self.retry_policy.max_attempts,
+                backoff.as_millis()
+            );
+
+            Some(backoff)
+        } else {
+            None
+        }
+    }
+
+    // NOTE: resolves a product relative api request into what goes on the
+    // wire, the same for the blocking and the async session
+    fn http_request(&self, request: &ApiRequest) -> HttpRequest {
+        let authorization: String = match &request.auth {
+            Auth::Product => format!("Bearer {}", &self.access_token),
+            Auth::Basic => format!(
+                "Basic {}",
+                base64::encode(format!(
+                    "{}:{}",
+                    &self.username, &self.password
+                ))
+            ),
+            Auth::Token(access_token) => format!("Bearer {}", access_token),
+        };
+
+        let mut headers: Vec<(String, String)> = vec![
+            ("Authorization".to_string(), authorization),
+            (
+                "X-Target-Environment".to_string(),
+                self.target_environment.clone(),
+            ),
+            (
+                "Ocp-Apim-Subscription-Key".to_string(),
+                self.subscription_key.clone(),
+            ),
+        ];
+
+        if let Some(body) = &request.body {
+            headers.push((
+                "Content-Type".to_string(),
+                request.content_type().to_string(),
+            ));
+            headers
+                .push(("Content-Length".to_string(), body.len().to_string()));
+        }
+
+        for (name, value) in &request.headers {
+            if !name.eq_ignore_ascii_case("Content-Type") {
+                headers.push((name.clone(), value.clone()));
+            }
+        }
+
+        HttpRequest {
+            method: request.method.clone(),
+            url: format!(
+                "{}{}/{}",
+                &self.base_url, self.product, &request.path
+            ),
+            headers,
+            body: request.body.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Session {
+    transport: Arc<dyn Transport>,
+    pub(crate) state: SessionState,
+}
+
+impl Session {
+    pub(crate) fn new(
+        config: &Config,
+        product: Product,
+        transport: Arc<dyn Transport>,
+    ) -> Result<Session> {
+        Ok(Session {
+            transport,
+            state: SessionState::new(config, product)?,
+        })
+    }
+
+    pub(crate) fn authorize(&mut self) -> Result<()> {
+        let Call { request, parse } = self.state.token_call();
+
+        // NOTE: sent directly, token requests never need a token themselves
+        let authorization: Authorization = parse(self.send(&request)?)?;
+
+        self.state.store(authorization);
+
+        Ok(())
+    }
+
+    pub(crate) fn callback_url<'a>(
+        &self,
+        callback_url: Option<&'a Url>,
+    ) -> Result<&'a Url> {
+        self.state.callback_url(callback_url)
+    }
+
+    pub(crate) fn execute<T>(&mut self, call: Call<T>) -> Result<T> {
+        let Call { request, parse } = call;
+
+        if self.state.token_expiring(&request) {
+            debug!("[mini-mtn-momo] access token expiring, reauthorizing...");
+
+            self.authorize()?;
+        }
+
+        let mut response: HttpResponse = self.send(&request)?;
+
+        if self.state.unauthorized(&request, response.status) {
+            debug!("currently unauthorized, attempting reauthorization...");
+
+            self.authorize()?;
+
+            response = self.send(&request)?;
+        }
+
+        parse(response)
+    }
+
+    fn send(&self, request: &ApiRequest) -> Result<HttpResponse> {
+        let http_request: HttpRequest = self.state.http_request(request);
+
+        let mut attempt: u32 = 1u32;
+
+        loop {
+            let result: Result<HttpResponse> =
+                self.transport.send(&http_request);
+
+            match self.state.retry_backoff(request, attempt, &result) {
+                Some(backoff) => thread::sleep(backoff),
+                None => return result,
+            }
+
+            attempt += 1u32;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct AsyncSession {
+    transport: Arc<dyn AsyncTransport>,
+    pub(crate) state: SessionState,
+}
+
+impl AsyncSession {
+    pub(crate) fn new(
+        config: &Config,
+        product: Product,
+        transport: Arc<dyn AsyncTransport>,
+    ) -> Result<AsyncSession> {
+        Ok(AsyncSession {
+            transport,
+            state: SessionState::new(config, product)?,
+        })
+    }
+
+    pub(crate) async fn authorize(&mut self) -> Result<()> {
+        let Call { request, parse } = self.state.token_call();
+
+        // NOTE: sent directly, token requests never need a token themselves
+        let authorization: Authorization = parse(self.send(&request).await?)?;
+
+        self.state.store(authorization);
+
+        Ok(())
+    }
+
+    pub(crate) fn callback_url<'a>(
+        &self,
+        callback_url: Option<&'a Url>,
+    ) -> Result<&'a Url> {
+        self.state.callback_url(callback_url)
+    }
+
+    pub(crate) async fn execute<T>(&mut self, call: Call<T>) -> Result<T> {
+        let Call { request, parse } = call;
+
+        if self.state.token_expiring(&request) {
+            debug!("[mini-mtn-momo] access token expiring, reauthorizing...");
+
+            self.authorize().await?;
+        }
+
+        let mut response: HttpResponse = self.send(&request).await?;
+
+        if self.state.unauthorized(&request, response.status) {
+            debug!("currently unauthorized, attempting reauthorization...");
+
+            self.authorize().await?;
+
+            response = self.send(&request).await?;
+        }
+
+        parse(response)
+    }
+
+    async fn send(&self, request: &ApiRequest) -> Result<HttpResponse> {
+        let http_request: HttpRequest = self.state.http_request(request);
+
+        let mut attempt: u32 = 1u32;
+
+        loop {
+            let result: Result<HttpResponse> =
+                self.transport.send(&http_request).await;
+
+            match self.state.retry_backoff(request, attempt, &result) {
+                Some(backoff) => tokio::time::delay_for(backoff).await,
+                None => return result,
+            }
+
+            attempt += 1u32;
+        }
+    }
+}
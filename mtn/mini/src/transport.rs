@@ -0,0 +1,616 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use http::StatusCode;
+use reqwest::{blocking, Method};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use common::*;
+
+use crate::call::Context;
+
+// NOTE: a fully resolved request, i.e. with absolute url, authorization
+// and all other headers set, ready to be put on the wire
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub(crate) fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_str(&self.body).map_err(|json_error| {
+            MomoError::Decode(format!("{}\n{}", json_error, self.body))
+        })
+    }
+
+    // NOTE: what failed and for which reference id or account holder
+    pub(crate) fn failure(
+        &self,
+        what: &str,
+        context: Option<&Context>,
+    ) -> MomoError {
+        let (reference_id, context): (Option<Uuid>, Option<String>) =
+            match context {
+                Some(Context::ReferenceId(reference_id)) => {
+                    (Some(*reference_id), None)
+                }
+                Some(Context::Described(context)) => {
+                    (None, Some(context.clone()))
+                }
+                None => (None, None),
+            };
+
+        MomoError::Api(Box::new(ApiError {
+            what: what.to_string(),
+            status: self.status,
+            reference_id,
+            context,
+            error_response: ErrorResponse::parse(&self.body),
+            body: self.body.clone(),
+        }))
+    }
+}
+
+// NOTE: reqwest errors are mapped by hand as MomoError lives in common
+pub(crate) fn network_error(reqwest_error: reqwest::Error) -> MomoError {
+    let failure: NetworkFailure = if reqwest_error.is_timeout() {
+        NetworkFailure::Timeout
+    } else if reqwest_error.is_connect() {
+        NetworkFailure::Connect
+    } else {
+        NetworkFailure::Other
+    };
+
+    MomoError::Network {
+        failure,
+        message: reqwest_error.to_string(),
+    }
+}
+
+// NOTE: sends requests on behalf of the blocking clients, swap it for
+// canned responses to test without the MTN sandbox
+pub trait Transport: fmt::Debug + Send + Sync {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse>;
+}
+
+#[derive(Debug)]
+pub struct ReqwestTransport {
+    http_client: blocking::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> Result<ReqwestTransport> {
+        let http_client: blocking::Client = blocking::Client::builder()
+            .http1_title_case_headers()
+            .build()
+            .map_err(network_error)?;
+
+        Ok(ReqwestTransport { http_client })
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let mut builder: blocking::RequestBuilder = self
+            .http_client
+            .request(request.method.clone(), &request.url);
+
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+
+        let response: blocking::Response =
+            builder.send().map_err(network_error)?;
+
+        Ok(HttpResponse {
+            status: response.status(),
+            body: response.text().map_err(network_error)?,
+        })
+    }
+}
+
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<HttpResponse>> + Send + 'a>>;
+
+// NOTE: the async counterpart of Transport used by AsyncClient
+pub trait AsyncTransport: fmt::Debug + Send + Sync {
+    fn send<'a>(&'a self, request: &'a HttpRequest) -> TransportFuture<'a>;
+}
+
+#[derive(Debug)]
+pub struct AsyncReqwestTransport {
+    http_client: reqwest::Client,
+}
+
+impl AsyncReqwestTransport {
+    pub fn new() -> Result<AsyncReqwestTransport> {
+        let http_client: reqwest::Client = reqwest::Client::builder()
+            .http1_title_case_headers()
+            .build()
+            .map_err(network_error)?;
+
+        Ok(AsyncReqwestTransport { http_client })
+    }
+}
+
+impl AsyncTransport for AsyncReqwestTransport {
+    fn send<'a>(&'a self, request: &'a HttpRequest) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let mut builder: reqwest::RequestBuilder = self
+                .http_client
+                .request(request.method.clone(), &request.url);
+
+            for (name, value) in &request.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+
+            if let Some(body) = &request.body {
+                builder = builder.body(body.clone());
+            }
+
+            let response: reqwest::Response =
+                builder.send().await.map_err(network_error)?;
+
+            Ok(HttpResponse {
+                status: response.status(),
+                body: response.text().await.map_err(network_error)?,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod mtn_momo_mini_transport_unit_tests {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use common::*;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        AsyncClient, Client, Config, Credentials, DisbursementClient, IClient,
+        IDisbursementClient, PaymentRequest, RetryPolicy, Transaction,
+        Transfer, GHANA,
+    };
+
+    const TOKEN: &str = r#"{
+        "access_token": "token-1",
+        "token_type": "access_token",
+        "expires_in": 3600
+    }"#;
+    const FRESH_TOKEN: &str = r#"{
+        "access_token": "token-2",
+        "token_type": "access_token",
+        "expires_in": 3600
+    }"#;
+    const BALANCE: &str = r#"{ "availableBalance": "419", "currency": "EUR" }"#;
+    const TRANSFER: &str = r#"{
+        "amount": "419",
+        "currency": "EUR",
+        "financialTransactionId": "363440463",
+        "externalId": "settlement-419",
+        "payee": { "partyIdType": "MSISDN", "partyId": "233542373722" },
+        "payerMessage": "settlement 419",
+        "payeeNote": "Settlement 419",
+        "status": "SUCCESSFUL"
+    }"#;
+
+    // NOTE: answers requests in order with the responses it was given and
+    // remembers every request it has seen
+    #[derive(Debug)]
+    struct CannedTransport {
+        responses: Mutex<VecDeque<HttpResponse>>,
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl CannedTransport {
+        fn new(responses: Vec<(StatusCode, &str)>) -> Arc<CannedTransport> {
+            Arc::new(CannedTransport {
+                responses: Mutex::new(
+                    responses
+                        .into_iter()
+                        .map(|(status, body)| HttpResponse {
+                            status,
+                            body: body.to_string(),
+                        })
+                        .collect(),
+                ),
+                requests: Mutex::new(vec![]),
+            })
+        }
+
+        fn requests(&self) -> Vec<HttpRequest> {
+            self.requests.lock().expect("mutex requests").clone()
+        }
+    }
+
+    impl Transport for CannedTransport {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+            self.requests
+                .lock()
+                .expect("mutex requests")
+                .push(request.clone());
+
+            self.responses
+                .lock()
+                .expect("mutex responses")
+                .pop_front()
+                .ok_or_else(|| MomoError::Network {
+                    failure: NetworkFailure::Other,
+                    message: format!("no canned response for {}", request.url),
+                })
+        }
+    }
+
+    impl AsyncTransport for CannedTransport {
+        fn send<'a>(&'a self, request: &'a HttpRequest) -> TransportFuture<'a> {
+            Box::pin(async move { Transport::send(self, request) })
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            username: String::from("collection-user"),
+            password: String::from("collection-password"),
+            subscription_key: String::from("collection-key"),
+            base_url: None,
+            callback_host: None,
+            disbursement: None,
+            remittance: None,
+            retry: Some(RetryPolicy {
+                initial_backoff: Duration::from_millis(1u64),
+                ..RetryPolicy::default()
+            }),
+        }
+    }
+
+    fn disbursement_config() -> Config {
+        Config {
+            disbursement: Some(Credentials {
+                username: String::from("disbursement-user"),
+                password: String::from("disbursement-password"),
+                subscription_key: String::from("disbursement-key"),
+            }),
+            ..config()
+        }
+    }
+
+    fn client(transport: &Arc<CannedTransport>) -> Client {
+        Client::with_transport(&config(), transport.clone()).expect("client")
+    }
+
+    async fn async_client(transport: &Arc<CannedTransport>) -> AsyncClient {
+        AsyncClient::with_transport(&config(), transport.clone())
+            .await
+            .expect("async client")
+    }
+
+    fn payment_request() -> PaymentRequest {
+        PaymentRequest {
+            amount: 419u64,
+            currency: Currency::Cedi,
+            payer: Party::from(
+                Msisdn::parse("0542373722", &GHANA, None).expect("msisdn"),
+            ),
+            payer_message: String::from("Order 419 at Nugget Digital"),
+            payee_note: String::from("order 419"),
+            external_id: String::from("order-419"),
+            reference_id: None,
+        }
+    }
+
+    #[test]
+    fn requesting_to_pay() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::ACCEPTED, ""),
+        ]);
+
+        let reference_id: Uuid = client(&transport)
+            .request_to_pay(&payment_request(), None)
+            .expect("request_to_pay");
+
+        let requests: Vec<HttpRequest> = transport.requests();
+
+        assert_eq!(requests.len(), 2usize);
+        assert!(requests[0].url.ends_with("collection/token/"));
+        assert!(requests[0]
+            .header("Authorization")
+            .expect("authorization")
+            .starts_with("Basic "));
+        assert!(requests[1].url.ends_with("collection/v1_0/requesttopay"));
+        assert_eq!(requests[1].header("Authorization"), Some("Bearer token-1"));
+        assert_eq!(
+            requests[1].header("X-Reference-Id"),
+            Some(reference_id.to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn requesting_to_pay_status() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (
+                StatusCode::OK,
+                r#"{
+                    "amount": "419",
+                    "currency": "EUR",
+                    "financialTransactionId": "1837462849",
+                    "externalId": "order-419",
+                    "payer": {
+                        "partyIdType": "MSISDN",
+                        "partyId": "233542373722"
+                    },
+                    "status": "SUCCESSFUL"
+                }"#,
+            ),
+        ]);
+
+        let transaction: Transaction = client(&transport)
+            .request_to_pay_status(&Uuid::new_v4())
+            .expect("request_to_pay_status");
+
+        assert_eq!(transaction.status, PaymentStatus::Resolved);
+        assert_eq!(transaction.payer.party_id, "233542373722");
+    }
+
+    #[test]
+    fn getting_balance() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::OK, BALANCE),
+        ]);
+
+        let balance: Balance =
+            client(&transport).get_balance().expect("get_balance");
+
+        assert_eq!(
+            balance,
+            serde_json::from_str::<Balance>(BALANCE).expect("balance")
+        );
+    }
+
+    #[test]
+    fn reauthorizing_once_after_401() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::UNAUTHORIZED, ""),
+            (StatusCode::OK, FRESH_TOKEN),
+            (StatusCode::OK, BALANCE),
+        ]);
+
+        client(&transport).get_balance().expect("get_balance");
+
+        let requests: Vec<HttpRequest> = transport.requests();
+
+        assert_eq!(requests.len(), 4usize);
+        assert!(requests[2].url.ends_with("collection/token/"));
+        assert_eq!(requests[3].header("Authorization"), Some("Bearer token-2"));
+    }
+
+    #[tokio::test]
+    async fn getting_balance_asynchronously() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::OK, BALANCE),
+        ]);
+
+        let balance: Balance = async_client(&transport)
+            .await
+            .get_balance()
+            .await
+            .expect("get_balance");
+
+        assert_eq!(
+            balance,
+            serde_json::from_str::<Balance>(BALANCE).expect("balance")
+        );
+    }
+
+    #[tokio::test]
+    async fn reauthorizing_asynchronously_once_after_401() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::UNAUTHORIZED, ""),
+            (StatusCode::OK, FRESH_TOKEN),
+            (StatusCode::SERVICE_UNAVAILABLE, ""),
+            (StatusCode::OK, BALANCE),
+        ]);
+
+        async_client(&transport)
+            .await
+            .get_balance()
+            .await
+            .expect("get_balance");
+
+        let requests: Vec<HttpRequest> = transport.requests();
+
+        assert_eq!(requests.len(), 5usize);
+        assert!(requests[2].url.ends_with("collection/token/"));
+        assert_eq!(requests[4].header("Authorization"), Some("Bearer token-2"));
+    }
+
+    #[tokio::test]
+    async fn requesting_transfer_status_asynchronously() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::OK, FRESH_TOKEN),
+            (StatusCode::OK, TRANSFER),
+        ]);
+
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let transfer: Transfer = AsyncClient::with_transport(
+            &disbursement_config(),
+            transport.clone(),
+        )
+        .await
+        .expect("async client")
+        .disbursements()
+        .expect("disbursements")
+        .transfer_status(&reference_id)
+        .await
+        .expect("transfer_status");
+
+        let requests: Vec<HttpRequest> = transport.requests();
+
+        assert_eq!(transfer.status, PaymentStatus::Resolved);
+        assert!(requests[1].url.ends_with("disbursement/token/"));
+        assert!(requests[2].url.ends_with(&format!(
+            "disbursement/v1_0/transfer/{}",
+            reference_id
+        )));
+        assert_eq!(requests[2].header("Authorization"), Some("Bearer token-2"));
+        assert_eq!(
+            requests[2].header("Ocp-Apim-Subscription-Key"),
+            Some("disbursement-key")
+        );
+    }
+
+    #[test]
+    fn giving_up_after_a_second_401() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::UNAUTHORIZED, ""),
+            (StatusCode::OK, FRESH_TOKEN),
+            (StatusCode::UNAUTHORIZED, ""),
+        ]);
+
+        assert!(client(&transport).get_balance().is_err());
+        assert_eq!(transport.requests().len(), 4usize);
+    }
+
+    #[test]
+    fn failing_on_a_duplicate_reference_id() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (
+                StatusCode::CONFLICT,
+                r#"{
+                    "code": "RESOURCE_ALREADY_EXIST",
+                    "message": "Duplicated reference id."
+                }"#,
+            ),
+        ]);
+
+        let reference_id: Uuid = Uuid::new_v4();
+
+        let request: PaymentRequest = PaymentRequest {
+            reference_id: Some(reference_id),
+            ..payment_request()
+        };
+
+        let momo_error: MomoError = client(&transport)
+            .request_to_pay(&request, None)
+            .expect_err("request_to_pay");
+
+        assert_eq!(
+            transport.requests()[1].header("X-Reference-Id"),
+            Some(reference_id.to_string().as_str())
+        );
+        assert_eq!(momo_error.status(), Some(StatusCode::CONFLICT));
+        assert_eq!(momo_error.reference_id(), Some(reference_id));
+        assert_eq!(
+            momo_error.code(),
+            Some(&FailureReason::ResourceAlreadyExist)
+        );
+        assert!(!momo_error.is_retryable());
+    }
+
+    #[test]
+    fn retrying_balance_on_transient_failures() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::INTERNAL_SERVER_ERROR, ""),
+            (StatusCode::OK, TOKEN),
+            (StatusCode::SERVICE_UNAVAILABLE, ""),
+            (StatusCode::OK, BALANCE),
+        ]);
+
+        client(&transport).get_balance().expect("get_balance");
+
+        let requests: Vec<HttpRequest> = transport.requests();
+
+        assert_eq!(requests.len(), 4usize);
+        assert!(requests[1].url.ends_with("collection/token/"));
+        assert!(requests[3].url.ends_with("collection/v1_0/account/balance"));
+    }
+
+    #[test]
+    fn retrying_transfer_status_on_transient_failures() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::BAD_GATEWAY, ""),
+            (StatusCode::OK, TRANSFER),
+        ]);
+
+        let transfer: Transfer = DisbursementClient::with_transport(
+            &disbursement_config(),
+            transport.clone(),
+        )
+        .expect("disbursement client")
+        .transfer_status(&Uuid::new_v4())
+        .expect("transfer_status");
+
+        let requests: Vec<HttpRequest> = transport.requests();
+
+        assert_eq!(transfer.status, PaymentStatus::Resolved);
+        assert_eq!(requests.len(), 3usize);
+        assert_eq!(requests[1].url, requests[2].url);
+    }
+
+    #[test]
+    fn giving_up_after_max_attempts() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::SERVICE_UNAVAILABLE, ""),
+            (StatusCode::SERVICE_UNAVAILABLE, ""),
+            (StatusCode::SERVICE_UNAVAILABLE, ""),
+        ]);
+
+        let momo_error: MomoError = client(&transport)
+            .request_to_pay_status(&Uuid::new_v4())
+            .expect_err("request_to_pay_status");
+
+        assert_eq!(momo_error.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(transport.requests().len(), 4usize);
+    }
+
+    #[test]
+    fn never_resending_requests_to_pay() {
+        let transport: Arc<CannedTransport> = CannedTransport::new(vec![
+            (StatusCode::OK, TOKEN),
+            (StatusCode::SERVICE_UNAVAILABLE, ""),
+        ]);
+
+        assert!(client(&transport)
+            .request_to_pay(&payment_request(), None)
+            .is_err());
+        assert_eq!(transport.requests().len(), 2usize);
+    }
+}
@@ -1,24 +1,52 @@
 use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
 
 use common::*;
 use lazy_static::lazy_static;
 use mini::*;
 use uuid::Uuid;
 
+// NOTE: reads e.g. MTN_MOMO_SANDBOX_DISBURSEMENT_USERNAME
+fn sandbox_credentials(product: &str) -> Option<Credentials> {
+    let var = |name: &str| -> Option<String> {
+        std::env::var(format!("MTN_MOMO_SANDBOX_{}_{}", product, name)).ok()
+    };
+
+    Some(Credentials {
+        username: var("USERNAME")?,
+        password: var("PASSWORD")?,
+        subscription_key: var("SUBSCRIPTION_KEY")?,
+    })
+}
+
 lazy_static! {
     static ref CONFIG: Config = Config {
-        username: env!("MTN_MOMO_SANDBOX_USERNAME").to_string(),
-        password: env!("MTN_MOMO_SANDBOX_PASSWORD").to_string(),
-        subscription_key: env!("MTN_MOMO_SANDBOX_SUBSCRIPTION_KEY").to_string(),
+        username: std::env::var("MTN_MOMO_SANDBOX_USERNAME")
+            .expect("MTN_MOMO_SANDBOX_USERNAME"),
+        password: std::env::var("MTN_MOMO_SANDBOX_PASSWORD")
+            .expect("MTN_MOMO_SANDBOX_PASSWORD"),
+        subscription_key: std::env::var("MTN_MOMO_SANDBOX_SUBSCRIPTION_KEY")
+            .expect("MTN_MOMO_SANDBOX_SUBSCRIPTION_KEY"),
         // for production pass https://momodeveloper.mtn.com/ as base_url
         // falls back to https://sandbox.momodeveloper.mtn.com/
         base_url: None,
         // for production pass your api hostname (nugget.digital)
         // falls back to www.mocky.io
         callback_host: None,
+        // each product has its own api user and subscription key, the
+        // disbursement and remittance tests fail without them
+        disbursement: sandbox_credentials("DISBURSEMENT"),
+        remittance: sandbox_credentials("REMITTANCE"),
+        // retries status lookups, balances and token fetches on 5xx and
+        // network failures, falls back to RetryPolicy::default()
+        retry: None,
     };
     static ref CLIENT: Mutex<Client> =
         Mutex::new(Client::new(&CONFIG).expect("client"));
+    static ref DISBURSEMENT_CLIENT: Mutex<DisbursementClient> = Mutex::new(
+        DisbursementClient::new(&CONFIG).expect("disbursement client")
+    );
     static ref GHANA: Country = Country {
         code: String::from("GH"),
         prefix: String::from("233"),
@@ -27,7 +55,7 @@ lazy_static! {
     static ref NIGERIA: Country = Country {
         code: String::from("NG"),
         prefix: String::from("234"),
-        non_prefix_digits: 8usize,
+        non_prefix_digits: 10usize,
     };
 }
 
@@ -48,6 +76,15 @@ fn part2_getting_balance() {
     let _balance: Balance = client.get_balance().expect("balance");
 }
 
+#[test]
+#[ignore]
+fn part2_getting_balance_in_a_currency() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let _balance: Balance =
+        client.get_balance_in(Currency::Cedi).expect("balance");
+}
+
 #[test]
 #[ignore]
 fn part3_request_to_pay_without_a_callback() {
@@ -56,8 +93,18 @@ fn part3_request_to_pay_without_a_callback() {
     let msisdn: Msisdn =
         Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
 
+    let request: PaymentRequest = PaymentRequest {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        payer: Party::from(msisdn),
+        payer_message: String::from("Order 419 at Nugget Digital"),
+        payee_note: String::from("order 419"),
+        external_id: String::from("order-419"),
+        reference_id: None,
+    };
+
     let _uuid: Uuid = client
-        .request_to_pay(419u64, Currency::Cedi, &msisdn, None)
+        .request_to_pay(&request, None)
         .expect("request_to_pay");
 }
 
@@ -69,12 +116,377 @@ fn part4_request_to_pay_with_a_callback() {
     let msisdn: Msisdn =
         Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
 
+    let request: PaymentRequest = PaymentRequest {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        payer: Party::from(msisdn),
+        payer_message: String::from("Order 420 at Nugget Digital"),
+        payee_note: String::from("order 420"),
+        external_id: String::from("order-420"),
+        reference_id: None,
+    };
+
     let _uuid: Uuid = client
-        .request_to_pay(
-            419u64,
-            Currency::Cedi,
-            &msisdn,
-            Some(&FALLBACK_CALLBACK_URL),
+        .request_to_pay(&request, Some(&FALLBACK_CALLBACK_URL))
+        .expect("request_to_pay");
+}
+
+#[test]
+fn part5_validating_an_account_holder() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let _active: bool = client
+        .validate_account_holder(&Party::from(msisdn))
+        .expect("validate_account_holder");
+}
+
+#[test]
+fn part6_getting_basic_user_info() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let _basic_user_info: BasicUserInfo = client
+        .get_basic_user_info(&msisdn)
+        .expect("get_basic_user_info");
+}
+
+#[test]
+#[ignore]
+fn part7_request_to_withdraw() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let request: PaymentRequest = PaymentRequest {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        payer: Party::from(msisdn),
+        payer_message: String::from("Cash out 421 at Nugget Digital"),
+        payee_note: String::from("cash out 421"),
+        external_id: String::from("cash-out-421"),
+        reference_id: None,
+    };
+
+    for version in &[ApiVersion::V1, ApiVersion::V2] {
+        let uuid: Uuid = client
+            .request_to_withdraw(&request, None, *version)
+            .expect("request_to_withdraw");
+
+        let _transaction: Transaction = client
+            .request_to_withdraw_status(&uuid)
+            .expect("request_to_withdraw_status");
+    }
+}
+
+#[test]
+#[ignore]
+fn part8_request_to_pay_delivery_notification() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let request: PaymentRequest = PaymentRequest {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        payer: Party::from(msisdn),
+        payer_message: String::from("Order 422 at Nugget Digital"),
+        payee_note: String::from("order 422"),
+        external_id: String::from("order-422"),
+        reference_id: None,
+    };
+
+    let uuid: Uuid = client
+        .request_to_pay(&request, None)
+        .expect("request_to_pay");
+
+    client
+        .request_to_pay_delivery_notification(
+            &uuid,
+            "Thanks, order #422 confirmed",
+            Some("en"),
         )
+        .expect("request_to_pay_delivery_notification");
+}
+
+#[test]
+#[ignore]
+fn part9_creating_and_cancelling_an_invoice() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let invoice: Invoice = Invoice {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        intended_payer: Party::from(
+            Msisdn::parse("0542373722", &GHANA, None).expect("msisdn"),
+        ),
+        payee: Party::from(
+            Msisdn::parse("0244000001", &GHANA, None).expect("msisdn"),
+        ),
+        validity_duration: Duration::from_secs(3600u64),
+        description: String::from("Invoice 423 for March"),
+        external_id: String::from("invoice-423"),
+        reference_id: None,
+    };
+
+    let uuid: Uuid = client
+        .create_invoice(&invoice, None)
+        .expect("create_invoice");
+
+    let _invoice_details: InvoiceDetails = client
+        .get_invoice_status(&uuid)
+        .expect("get_invoice_status");
+
+    client
+        .cancel_invoice(&uuid, &invoice.external_id)
+        .expect("cancel_invoice");
+}
+
+#[test]
+#[ignore]
+fn part10_creating_a_pre_approval() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let pre_approval: PreApproval = PreApproval {
+        payer: Party::from(msisdn),
+        payer_currency: Currency::Cedi,
+        payer_message: String::from("Monthly Nugget Digital plan"),
+        validity_time: Duration::from_secs(3600u64),
+        reference_id: None,
+    };
+
+    let uuid: Uuid = client
+        .create_pre_approval(&pre_approval, None)
+        .expect("create_pre_approval");
+
+    let _pre_approval_details: PreApprovalDetails = client
+        .get_pre_approval_status(&uuid)
+        .expect("get_pre_approval_status");
+
+    let _approved_pre_approvals: Vec<ApprovedPreApproval> = client
+        .get_approved_pre_approvals(&pre_approval.payer)
+        .expect("get_approved_pre_approvals");
+}
+
+#[test]
+#[ignore]
+fn part11_creating_a_bill_payment() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let bill_payment: BillPayment = BillPayment {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        customer_reference: CustomerReference::parse("METER-0042")
+            .expect("customer reference"),
+        service_provider_name: ServiceProviderName::parse("ecg.prepaid")
+            .expect("service provider name"),
+        receiver_message: String::from("Meter 0042 top up"),
+        sender_note: String::from("electricity March"),
+        external_transaction_id: String::from("bill-424"),
+        coupon_id: None,
+        product_id: None,
+        product_offering_id: None,
+        max_number_of_retries: None,
+        include_sender_charges: false,
+        reference_id: None,
+    };
+
+    let uuid: Uuid = client
+        .create_payment(&bill_payment, None)
+        .expect("create_payment");
+
+    let _bill_payment_details: BillPaymentDetails = client
+        .get_payment_status(&uuid)
+        .expect("get_payment_status");
+}
+
+#[test]
+#[ignore]
+fn part12_transferring_a_disbursement() {
+    let mut client: MutexGuard<DisbursementClient> = DISBURSEMENT_CLIENT
+        .lock()
+        .expect("mutex disbursement client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let request: TransferRequest = TransferRequest {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        payee: Party::from(msisdn),
+        payer_message: String::from("payout 419"),
+        payee_note: String::from("Nugget Digital payout 419"),
+        external_id: String::from("payout-419"),
+        reference_id: None,
+    };
+
+    let reference_id: Uuid = client.transfer(&request, None).expect("transfer");
+
+    let transfer: Transfer = client
+        .transfer_status(&reference_id)
+        .expect("transfer status");
+
+    assert_eq!(transfer.payee.party_id, "233542373722");
+}
+
+// NOTE: the sandbox settles requests to pay asynchronously, gives up with
+// the last status seen once it stops waiting
+fn await_final_status(client: &mut Client, reference_id: &Uuid) -> Transaction {
+    let mut transaction: Transaction = client
+        .request_to_pay_status(reference_id)
+        .expect("request_to_pay_status");
+
+    for _ in 0usize..10usize {
+        if transaction.status.is_final() {
+            break;
+        }
+
+        thread::sleep(Duration::from_secs(2u64));
+
+        transaction = client
+            .request_to_pay_status(reference_id)
+            .expect("request_to_pay_status");
+    }
+
+    transaction
+}
+
+#[test]
+#[ignore]
+fn part13_refunding_a_payment() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let request: PaymentRequest = PaymentRequest {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        payer: Party::from(msisdn),
+        payer_message: String::from("Order 421 at Nugget Digital"),
+        payee_note: String::from("order 421"),
+        external_id: String::from("order-421"),
+        reference_id: None,
+    };
+
+    let reference_id_to_refund: Uuid = client
+        .request_to_pay(&request, None)
         .expect("request_to_pay");
+
+    let original: Transaction =
+        await_final_status(&mut client, &reference_id_to_refund);
+
+    if original.status != PaymentStatus::Resolved {
+        eprintln!(
+            "skipping refund, payment {} ended up {}",
+            reference_id_to_refund, original.status
+        );
+
+        return;
+    }
+
+    let refund: RefundRequest = RefundRequest {
+        amount: 19u64,
+        reference_id_to_refund,
+        payer_message: String::from("refund order 421"),
+        payee_note: String::from("Partial refund for order 421"),
+        external_id: String::from("refund-421"),
+        reference_id: None,
+    };
+
+    let mut disbursement_client: MutexGuard<DisbursementClient> =
+        DISBURSEMENT_CLIENT
+            .lock()
+            .expect("mutex disbursement client");
+
+    let reference_id: Uuid = disbursement_client
+        .refund(&refund, &original, None, ApiVersion::V2)
+        .expect("refund");
+
+    let refunded: Refund = disbursement_client
+        .refund_status(&reference_id, &reference_id_to_refund)
+        .expect("refund_status");
+
+    assert_eq!(refunded.reference_id_to_refund, reference_id_to_refund);
+    assert_eq!(refunded.transfer.reference_id, reference_id);
+}
+
+#[test]
+#[ignore]
+fn part14_sending_a_cash_transfer() {
+    let mut client: RemittanceClient =
+        RemittanceClient::new(&CONFIG).expect("remittance client");
+
+    let cash_transfer: CashTransfer = CashTransfer {
+        amount: 419u64,
+        currency: Currency::Cedi,
+        payee: Party::from(
+            Msisdn::parse("0542373722", &GHANA, None).expect("msisdn"),
+        ),
+        originating_country: String::from("GB"),
+        original_amount: 25u64,
+        original_currency: String::from("GBP"),
+        originator: Originator {
+            first_name: String::from("Kwame"),
+            surname: String::from("Mensah"),
+            identification_type: IdentificationType::Passport,
+            identification_number: String::from("123456789"),
+            language_code: String::from("en"),
+            email: None,
+            msisdn: None,
+        },
+        payer_message: String::from("family support"),
+        payee_note: String::from("From Kwame in London"),
+        external_id: String::from("corridor-419"),
+        reference_id: None,
+    };
+
+    let reference_id: Uuid = client
+        .cash_transfer(&cash_transfer, None)
+        .expect("cash_transfer");
+
+    let _cash_transfer_details: CashTransferDetails = client
+        .cash_transfer_status(&reference_id)
+        .expect("cash_transfer_status");
+
+    let _balance: Balance = client.get_balance().expect("balance");
+}
+
+#[test]
+#[ignore]
+fn part15_getting_user_info_with_consent() {
+    let mut client: MutexGuard<Client> = CLIENT.lock().expect("mutex client");
+
+    let msisdn: Msisdn =
+        Msisdn::parse("0542373722", &GHANA, None).expect("msisdn");
+
+    let consent_request: ConsentRequest = client
+        .bc_authorize(&msisdn, &[Scope::OpenId, Scope::Profile], None)
+        .expect("bc_authorize");
+
+    let consent_token: ConsentToken = client
+        .consent_token(&consent_request)
+        .expect("consent_token");
+
+    let _user_info: UserInfoWithConsent = client
+        .get_user_info_with_consent(&consent_token)
+        .expect("get_user_info_with_consent");
+}
+
+#[tokio::test]
+#[ignore]
+async fn part16_getting_balance_asynchronously() {
+    let mut client: AsyncClient =
+        AsyncClient::new(&CONFIG).await.expect("async client");
+
+    let _balance: Balance = client.get_balance().await.expect("balance");
 }